@@ -0,0 +1,504 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use lesspass_otp::keyring::Profile;
+use lesspass_otp::Algorithm;
+
+/// Generate LessPass passwords from a master password, a site, a login and a counter.
+#[derive(Debug, Parser)]
+#[command(name = "lesspass-otp", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: Global,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Options shared by every subcommand.
+#[derive(Debug, Args)]
+pub struct Global {
+    /// Profiles file, instead of `~/.config/lesspass-otp/profiles.toml`.
+    #[arg(long, global = true)]
+    pub profiles: Option<PathBuf>,
+
+    /// Print a JSON object instead of the raw value, to be used by scripts.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Use the master password stored in the OS keychain, instead of asking it.
+    #[cfg(feature = "native")]
+    #[arg(long, global = true)]
+    pub keychain: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Derive the password of a login on a site.
+    Generate(Generate),
+
+    /// Compute the TOTP token of the current time.
+    Totp(Totp),
+
+    /// Compute the HOTP token of a counter.
+    Hotp(Hotp),
+
+    /// Encrypt or decrypt an OTP secret with the master password.
+    #[command(subcommand)]
+    Secret(Secret),
+
+    /// Manage the OTP provisioning.
+    #[command(subcommand)]
+    Otp(OtpCommand),
+
+    /// Pick a profile or a new site, and the settings, in the terminal.
+    Interactive(Interactive),
+
+    /// Print the completion script of a shell.
+    Completions(Completions),
+
+    /// Store the master password in the OS keychain, or remove it.
+    #[cfg(feature = "native")]
+    #[command(subcommand)]
+    Master(Master),
+}
+
+#[derive(Debug, Args)]
+pub struct Generate {
+    /// Name of the profile providing the parameters, overridden by the other arguments.
+    pub profile: Option<String>,
+
+    /// Site of the password, like `example.com`.
+    #[arg(long, required_unless_present = "profile")]
+    pub site: Option<String>,
+
+    /// Login on the site.
+    #[arg(long, required_unless_present = "profile")]
+    pub login: Option<String>,
+
+    /// Counter, to increment to change the password [default: 1].
+    #[arg(long)]
+    pub counter: Option<u32>,
+
+    /// Length of the password [default: 16].
+    #[arg(long)]
+    pub length: Option<u8>,
+
+    /// Characters types used in the password [default: lowercase,uppercase,numbers,symbols].
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub charset: Option<Vec<Charset>>,
+
+    /// Algorithm used to derive the password [default: sha256].
+    #[arg(long, value_enum)]
+    pub algorithm: Option<HashAlgorithm>,
+
+    /// Number of PBKDF2 iterations [default: 100000].
+    #[arg(long)]
+    pub iterations: Option<u32>,
+
+    /// Copy the password to the clipboard instead of printing it.
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Do not ask to confirm the fingerprint of the master password.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+impl Generate {
+    /// Override the parameters of the `profile`, or the default ones, with the arguments.
+    pub fn resolve(&self, profile: Option<Profile>) -> Profile {
+        let mut profile = profile.unwrap_or_default();
+        if let Some(site) = &self.site {
+            profile.site = site.clone();
+        }
+        if let Some(login) = &self.login {
+            profile.login = login.clone();
+        }
+        if let Some(counter) = self.counter {
+            profile.counter = counter;
+        }
+        if let Some(length) = self.length {
+            profile.length = length;
+        }
+        if let Some(charset) = &self.charset {
+            profile.lowercase = charset.contains(&Charset::Lowercase);
+            profile.uppercase = charset.contains(&Charset::Uppercase);
+            profile.numbers = charset.contains(&Charset::Numbers);
+            profile.symbols = charset.contains(&Charset::Symbols);
+        }
+        profile.algorithm = self
+            .algorithm
+            .map(Algorithm::from)
+            .or(profile.algorithm)
+            .or(Some(Algorithm::SHA256));
+        if let Some(iterations) = self.iterations {
+            profile.iterations = Some(iterations);
+        }
+        profile
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Totp {
+    #[command(flatten)]
+    pub otp: OtpArgs,
+
+    /// Period of validity of a token, in seconds [default: 30].
+    #[arg(long)]
+    pub period: Option<u32>,
+
+    /// Keep displaying the current token and its remaining validity, until interrupted.
+    #[arg(long)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Hotp {
+    #[command(flatten)]
+    pub otp: OtpArgs,
+
+    /// Counter of the token.
+    #[arg(long)]
+    pub counter: u64,
+}
+
+/// Secret and format of the OTP tokens.
+#[derive(Debug, Args)]
+pub struct OtpArgs {
+    /// Name of the profile containing the encrypted secret.
+    #[arg(long, conflicts_with_all = ["secret_file", "encrypted_secret"])]
+    pub profile: Option<String>,
+
+    /// File containing the base32 encoded secret.
+    #[arg(long, required_unless_present_any = ["encrypted_secret", "profile"])]
+    pub secret_file: Option<PathBuf>,
+
+    /// Secret encrypted with the master password, base64 encoded.
+    #[arg(long, conflicts_with = "secret_file", requires_all = ["site", "login"])]
+    pub encrypted_secret: Option<String>,
+
+    /// Site of the encrypted secret.
+    #[arg(long)]
+    pub site: Option<String>,
+
+    /// Login of the encrypted secret.
+    #[arg(long)]
+    pub login: Option<String>,
+
+    /// Number of digits of a token [default: 6].
+    #[arg(long)]
+    pub digits: Option<u8>,
+
+    /// Algorithm of the HMAC [default: sha1].
+    #[arg(long, value_enum)]
+    pub algorithm: Option<OtpAlgorithm>,
+
+    /// Copy the token to the clipboard instead of printing it.
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Do not ask to confirm the fingerprint of the master password.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OtpCommand {
+    /// Print the provisioning QR code, to be scanned by an authenticator application.
+    Qr(Qr),
+}
+
+#[derive(Debug, Args)]
+pub struct Qr {
+    #[command(flatten)]
+    pub otp: OtpArgs,
+
+    /// Account name displayed by the authenticator.
+    #[arg(long, required_unless_present = "profile")]
+    pub label: Option<String>,
+
+    /// Provider or service of the account.
+    #[arg(long)]
+    pub issuer: Option<String>,
+
+    /// Period of validity of a TOTP token, in seconds [default: 30].
+    #[arg(long, conflicts_with = "counter")]
+    pub period: Option<u32>,
+
+    /// Initial counter of a HOTP, the QR code is a TOTP one if missing.
+    #[arg(long)]
+    pub counter: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Secret {
+    /// Encrypt a base32 encoded secret, and print it base64 encoded.
+    Encrypt(SecretArgs),
+
+    /// Decrypt a base64 encoded encrypted secret, and print it base32 encoded.
+    Decrypt(SecretArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SecretArgs {
+    /// Site of the secret.
+    #[arg(long)]
+    pub site: String,
+
+    /// Login of the secret.
+    #[arg(long)]
+    pub login: String,
+
+    /// File to read the secret from, instead of the standard input.
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+
+    /// The secret is a HOTP one, instead of a TOTP.
+    #[arg(long)]
+    pub hotp: bool,
+
+    /// Do not ask to confirm the fingerprint of the master password.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Interactive {
+    /// Copy the password or the token to the clipboard instead of printing it.
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Do not ask to confirm the fingerprint of the master password.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Completions {
+    /// Shell of the completion script, like `bash`, `zsh` or `fish`.
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[cfg(feature = "native")]
+#[derive(Debug, Subcommand)]
+pub enum Master {
+    /// Ask the master password, and store it in the OS keychain to be used with
+    /// `--keychain`.
+    Store {
+        /// Do not ask to confirm the fingerprint of the master password.
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Remove the master password from the OS keychain.
+    Forget,
+}
+
+/// Characters type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Charset {
+    Lowercase,
+    Uppercase,
+    Numbers,
+    Symbols,
+}
+
+/// Algorithm usable to derive a password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+    #[value(name = "sha3-256")]
+    Sha3_256,
+    #[value(name = "sha3-384")]
+    Sha3_384,
+    #[value(name = "sha3-512")]
+    Sha3_512,
+}
+
+impl From<HashAlgorithm> for Algorithm {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Self::SHA256,
+            HashAlgorithm::Sha384 => Self::SHA384,
+            HashAlgorithm::Sha512 => Self::SHA512,
+            HashAlgorithm::Sha3_256 => Self::SHA3_256,
+            HashAlgorithm::Sha3_384 => Self::SHA3_384,
+            HashAlgorithm::Sha3_512 => Self::SHA3_512,
+        }
+    }
+}
+
+/// Algorithm usable to compute an OTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<OtpAlgorithm> for Algorithm {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => Self::SHA1,
+            OtpAlgorithm::Sha256 => Self::SHA256,
+            OtpAlgorithm::Sha512 => Self::SHA512,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use lesspass_otp::charset::{CharacterSet, LowerCase, Numbers, Symbols, UpperCase};
+    use lesspass_otp::Settings;
+
+    fn generate(args: &[&str]) -> Generate {
+        let mut argv = vec!["lesspass-otp", "generate"];
+        argv.extend_from_slice(args);
+        match Cli::try_parse_from(argv).unwrap().command {
+            Command::Generate(generate) => generate,
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn default_settings() {
+        let generate = generate(&["--site", "example.com", "--login", "me"]);
+        assert!(!generate.copy);
+
+        let profile = generate.resolve(None);
+        assert_eq!(profile.counter, 1);
+        let settings = profile.settings();
+        assert_eq!(settings.get_password_len(), 16);
+        assert_eq!(settings.get_iterations(), 100_000);
+        assert_eq!(settings.get_algorithm(), Some(Algorithm::SHA256));
+        assert_eq!(
+            settings.get_characterset(),
+            Settings::default().get_characterset()
+        );
+    }
+
+    #[test]
+    fn custom_settings() {
+        let settings = generate(&[
+            "--site",
+            "example.com",
+            "--login",
+            "me",
+            "--length",
+            "20",
+            "--charset",
+            "lowercase,numbers",
+            "--algorithm",
+            "sha3-512",
+        ])
+        .resolve(None)
+        .settings();
+        assert_eq!(settings.get_password_len(), 20);
+        assert_eq!(settings.get_algorithm(), Some(Algorithm::SHA3_512));
+        assert_eq!(
+            settings.get_characterset(),
+            &CharacterSet::new(
+                LowerCase::Using,
+                UpperCase::NotUsing,
+                Numbers::Using,
+                Symbols::NotUsing
+            )
+        );
+    }
+
+    #[test]
+    fn missing_site() {
+        assert!(Cli::try_parse_from(["lesspass-otp", "generate", "--login", "me"]).is_err());
+    }
+
+    #[test]
+    fn override_profile() {
+        let mut profile = Profile::new("github", "github.com", "me");
+        profile.length = 20;
+        profile.symbols = false;
+        profile.algorithm = Some(Algorithm::SHA512);
+
+        let resolved = generate(&["github"]).resolve(Some(profile.clone()));
+        assert_eq!(resolved, profile);
+
+        let resolved = generate(&["github", "--login", "other", "--charset", "numbers"])
+            .resolve(Some(profile));
+        assert_eq!(resolved.site, "github.com");
+        assert_eq!(resolved.login, "other");
+        assert_eq!(resolved.length, 20);
+        assert!(!resolved.lowercase && resolved.numbers);
+        assert_eq!(resolved.algorithm, Some(Algorithm::SHA512));
+    }
+
+    #[test]
+    fn completions_shell() {
+        match Cli::try_parse_from(["lesspass-otp", "completions", "zsh"])
+            .unwrap()
+            .command
+        {
+            Command::Completions(completions) => assert_eq!(completions.shell, Shell::Zsh),
+            command => panic!("unexpected command {:?}", command),
+        }
+        assert!(Cli::try_parse_from(["lesspass-otp", "completions", "cmd"]).is_err());
+    }
+
+    #[test]
+    fn otp_secret_source() {
+        let cli = Cli::try_parse_from(["lesspass-otp", "totp", "--secret-file", "seed", "--watch"])
+            .unwrap();
+        match cli.command {
+            Command::Totp(totp) => {
+                assert!(!cli.global.json);
+                assert_eq!(totp.otp.secret_file, Some(PathBuf::from("seed")));
+                assert_eq!(totp.otp.algorithm, None);
+                assert_eq!(totp.period, None);
+                assert!(totp.watch);
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        // A secret is required
+        assert!(Cli::try_parse_from(["lesspass-otp", "hotp", "--counter", "1"]).is_err());
+        // An encrypted secret needs the site and the login
+        assert!(Cli::try_parse_from([
+            "lesspass-otp",
+            "hotp",
+            "--counter",
+            "1",
+            "--encrypted-secret",
+            "AAAA"
+        ])
+        .is_err());
+        assert!(Cli::try_parse_from([
+            "lesspass-otp",
+            "hotp",
+            "--counter",
+            "1",
+            "--encrypted-secret",
+            "AAAA",
+            "--site",
+            "example.com",
+            "--login",
+            "me"
+        ])
+        .is_ok());
+        // A profile is enough, and the global options are accepted after the subcommand
+        assert!(Cli::try_parse_from([
+            "lesspass-otp",
+            "hotp",
+            "--counter",
+            "1",
+            "--profile",
+            "github"
+        ])
+        .is_ok());
+    }
+}
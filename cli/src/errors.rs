@@ -0,0 +1,80 @@
+use std::{fmt, io};
+
+use lesspass_otp::LessPassError;
+
+/// Errors that can be return by the command line interface.
+#[derive(Debug)]
+pub enum CliError {
+    /// Error from the library.
+    LessPass(LessPassError),
+
+    /// Error while reading or writing the terminal or a file.
+    Io(io::Error),
+
+    /// The clipboard cannot be used.
+    Clipboard(String),
+
+    /// The encrypted secret is not valid base64.
+    InvalidBase64,
+
+    /// The QR code cannot be generated.
+    QrCode(String),
+
+    /// The user refused the fingerprint of the master password.
+    Aborted,
+
+    /// The master password is empty.
+    EmptyMaster,
+
+    /// The profiles file cannot be parsed.
+    InvalidProfiles(String),
+
+    /// No profile has this name.
+    UnknownProfile(String),
+
+    /// The profile has no OTP.
+    NoOtp(String),
+
+    /// The OS keychain cannot be used.
+    #[cfg(feature = "native")]
+    Keychain(String),
+}
+
+impl From<LessPassError> for CliError {
+    fn from(error: LessPassError) -> Self {
+        Self::LessPass(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<dialoguer::Error> for CliError {
+    fn from(error: dialoguer::Error) -> Self {
+        match error {
+            dialoguer::Error::IO(error) => Self::Io(error),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LessPass(error) => error.fmt(f),
+            Self::Io(error) => error.fmt(f),
+            Self::Clipboard(error) => write!(f, "Cannot use the clipboard: {}", error),
+            Self::InvalidBase64 => f.write_str("The encrypted secret is not valid base64."),
+            Self::QrCode(error) => write!(f, "Cannot generate the QR code: {}", error),
+            Self::Aborted => f.write_str("Aborted."),
+            Self::EmptyMaster => f.write_str("The master password cannot be empty."),
+            Self::InvalidProfiles(error) => write!(f, "Invalid profiles file: {}", error),
+            Self::UnknownProfile(name) => write!(f, "Unknown profile \"{}\".", name),
+            Self::NoOtp(name) => write!(f, "The profile \"{}\" has no OTP.", name),
+            #[cfg(feature = "native")]
+            Self::Keychain(error) => write!(f, "Cannot use the OS keychain: {}", error),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use serde_json::json;
+
+use lesspass_otp::keyring::Profile;
+
+use crate::args::{Generate, Global};
+use crate::errors::CliError;
+use crate::{master, output, profiles};
+
+/// Derive the password and print or copy it.
+pub fn run(args: &Generate, global: &Global) -> Result<(), CliError> {
+    let profile = match &args.profile {
+        Some(name) => Some(profiles::find(global.profiles.as_deref(), name)?),
+        None => None,
+    };
+    password(&args.resolve(profile), args.yes, args.copy, global)
+}
+
+/// Derive the password of the `profile` and print or copy it.
+pub fn password(profile: &Profile, yes: bool, copy: bool, global: &Global) -> Result<(), CliError> {
+    let (password, fingerprint) = master::with_master(global, yes, false, |lesspass| {
+        Ok((
+            profile.password(lesspass)?,
+            master::fingerprint_json(lesspass),
+        ))
+    })?;
+
+    if global.json {
+        let value = json!({
+            "password": password,
+            "fingerprint": fingerprint,
+            "entropy": profile.settings().get_entropy_bits(),
+        });
+        output::json(value, "password", copy)
+    } else {
+        output::secret(&password, copy)
+    }
+}
@@ -0,0 +1,153 @@
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+
+use lesspass_otp::keyring::{Keyring, Profile};
+
+use crate::args::{Global, Interactive};
+use crate::errors::CliError;
+use crate::{generate, otp, profiles};
+
+/// Characters types, in the order of the toggles.
+const CHARSETS: [&str; 4] = ["Lowercase", "Uppercase", "Numbers", "Symbols"];
+
+/// Ask the site, the login and the settings in the terminal, then print or copy the
+/// password, or the OTP token of the profile.
+pub fn run(args: &Interactive, global: &Global) -> Result<(), CliError> {
+    let theme = ColorfulTheme::default();
+    let keyring = profiles::load(global.profiles.as_deref())?;
+
+    let mut profile = match pick_profile(&theme, &keyring)? {
+        Some(profile) => profile.clone(),
+        None => new_profile(&theme)?,
+    };
+
+    if profile.otp.is_some() {
+        let choice = Select::with_theme(&theme)
+            .with_prompt("Generate")
+            .items(&["Password", "OTP token"])
+            .default(0)
+            .interact()?;
+        if choice == 1 {
+            return token(&theme, profile, args, global);
+        }
+    }
+
+    if profile.name.is_empty()
+        || Confirm::with_theme(&theme)
+            .with_prompt("Change the settings?")
+            .default(false)
+            .interact()?
+    {
+        edit_settings(&theme, &mut profile)?;
+    }
+
+    generate::password(&profile, args.yes, args.copy, global)
+}
+
+/// Select a profile of the `keyring`, `None` to enter a new site.
+fn pick_profile<'a>(
+    theme: &ColorfulTheme,
+    keyring: &'a Keyring,
+) -> Result<Option<&'a Profile>, CliError> {
+    if keyring.profiles.is_empty() {
+        return Ok(None);
+    }
+
+    let mut items = keyring
+        .profiles
+        .iter()
+        .map(|profile| format!("{} ({} on {})", profile.name, profile.login, profile.site))
+        .collect::<Vec<_>>();
+    items.push("Another site".to_owned());
+
+    let choice = Select::with_theme(theme)
+        .with_prompt("Profile")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    Ok(keyring.profiles.get(choice))
+}
+
+/// Ask the site and the login of a profile with the default settings.
+fn new_profile(theme: &ColorfulTheme) -> Result<Profile, CliError> {
+    let site: String = Input::with_theme(theme)
+        .with_prompt("Site")
+        .interact_text()?;
+    let login: String = Input::with_theme(theme)
+        .with_prompt("Login")
+        .interact_text()?;
+    Ok(Profile::new("", site.trim(), login.trim()))
+}
+
+/// Ask the counter, the length and the characters types of the password.
+fn edit_settings(theme: &ColorfulTheme, profile: &mut Profile) -> Result<(), CliError> {
+    profile.counter = Input::with_theme(theme)
+        .with_prompt("Counter")
+        .default(profile.counter)
+        .interact_text()?;
+    profile.length = Input::with_theme(theme)
+        .with_prompt("Length")
+        .default(profile.length)
+        .interact_text()?;
+
+    let selected = MultiSelect::with_theme(theme)
+        .with_prompt("Characters")
+        .items(&CHARSETS)
+        .defaults(&charsets(profile))
+        .interact()?;
+    set_charsets(profile, &selected);
+    Ok(())
+}
+
+/// Ask the counter of a HOTP, and print or copy the token of the profile.
+fn token(
+    theme: &ColorfulTheme,
+    profile: Profile,
+    args: &Interactive,
+    global: &Global,
+) -> Result<(), CliError> {
+    let loaded = otp::from_profile(profile, global, args.yes)?;
+    match loaded.provisioning().and_then(|p| p.get_counter()) {
+        Some(initial) => {
+            let counter = Input::with_theme(theme)
+                .with_prompt("Counter")
+                .default(initial)
+                .interact_text()?;
+            otp::print_hotp(&loaded, counter, args.copy, global)
+        }
+        None => otp::print_totp(&loaded, args.copy, global),
+    }
+}
+
+/// Characters types used by the `profile`, in the order of [`CHARSETS`].
+fn charsets(profile: &Profile) -> [bool; 4] {
+    [
+        profile.lowercase,
+        profile.uppercase,
+        profile.numbers,
+        profile.symbols,
+    ]
+}
+
+/// Use the characters types whose index in [`CHARSETS`] are `selected`.
+fn set_charsets(profile: &mut Profile, selected: &[usize]) {
+    profile.lowercase = selected.contains(&0);
+    profile.uppercase = selected.contains(&1);
+    profile.numbers = selected.contains(&2);
+    profile.symbols = selected.contains(&3);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charsets_toggles() {
+        let mut profile = Profile::default();
+        assert_eq!(charsets(&profile), [true; 4]);
+
+        set_charsets(&mut profile, &[0, 2]);
+        assert_eq!(charsets(&profile), [true, false, true, false]);
+        assert!(!profile.uppercase && !profile.symbols);
+    }
+}
@@ -0,0 +1,35 @@
+use keyring::Entry;
+use zeroize::Zeroizing;
+
+use crate::errors::CliError;
+
+/// Service and user naming the master password in the credential store.
+const SERVICE: &str = "lesspass-otp";
+const USER: &str = "master";
+
+/// Store the `master` password in the credential store of the OS: the Secret Service, the
+/// macOS Keychain or the Windows Credential Manager.
+pub fn store(master: &str) -> Result<(), CliError> {
+    entry()?.set_password(master).map_err(keychain_error)
+}
+
+/// Retrieve the stored master password.
+pub fn load() -> Result<Zeroizing<String>, CliError> {
+    entry()?
+        .get_password()
+        .map(Zeroizing::new)
+        .map_err(keychain_error)
+}
+
+/// Remove the stored master password.
+pub fn forget() -> Result<(), CliError> {
+    entry()?.delete_credential().map_err(keychain_error)
+}
+
+fn entry() -> Result<Entry, CliError> {
+    Entry::new(SERVICE, USER).map_err(keychain_error)
+}
+
+fn keychain_error(error: keyring::Error) -> CliError {
+    CliError::Keychain(error.to_string())
+}
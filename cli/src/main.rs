@@ -0,0 +1,110 @@
+#![deny(missing_debug_implementations)]
+#![deny(trivial_numeric_casts)]
+#![deny(unused_extern_crates)]
+#![deny(unused_qualifications)]
+
+//! Command line interface of `lesspass-otp`.
+//!
+//! ```text
+//! lesspass-otp generate --site example.com --login me@example.com --length 20
+//! lesspass-otp totp --secret-file ~/.seed --watch
+//! lesspass-otp otp qr --secret-file ~/.seed --label me@example.com --issuer Example
+//! lesspass-otp secret encrypt --site example.com --login me@example.com --file ~/.seed
+//! lesspass-otp generate github
+//! lesspass-otp totp --profile github
+//! lesspass-otp generate github --json
+//! lesspass-otp interactive
+//! lesspass-otp completions bash > /etc/bash_completion.d/lesspass-otp
+//! ```
+//!
+//! The profiles are read from `~/.config/lesspass-otp/profiles.toml`, they never contain
+//! the master password:
+//!
+//! ```toml
+//! [[profiles]]
+//! name = "github"
+//! site = "github.com"
+//! login = "me@example.com"
+//! length = 20
+//! symbols = false
+//!
+//! [profiles.otp]
+//! # Output of `lesspass-otp secret encrypt`
+//! secret = "/yW3Z9NhGYtU1Ht7vDq3bxlPo2X/m664DGPID/YlzGw="
+//! provisioning = { label = "me@example.com", issuer = "GitHub" }
+//! ```
+//!
+//! With the `native` feature, the master password can be stored in the OS keychain, and
+//! used instead of asking it with `--keychain`:
+//!
+//! ```text
+//! lesspass-otp master store
+//! lesspass-otp generate github --keychain
+//! lesspass-otp master forget
+//! ```
+
+use std::io::{self, Write};
+use std::process;
+
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+
+use crate::args::{Cli, Command, OtpCommand, Secret};
+use crate::errors::CliError;
+
+mod args;
+mod errors;
+mod generate;
+mod interactive;
+#[cfg(feature = "native")]
+mod keychain;
+mod master;
+mod otp;
+mod output;
+mod profiles;
+mod secret;
+
+fn main() {
+    let cli = Cli::parse();
+
+    let global = &cli.global;
+    let result = match &cli.command {
+        Command::Generate(args) => generate::run(args, global),
+        Command::Totp(args) => otp::totp(args, global),
+        Command::Hotp(args) => otp::hotp(args, global),
+        Command::Secret(Secret::Encrypt(args)) => secret::encrypt(args, global),
+        Command::Secret(Secret::Decrypt(args)) => secret::decrypt(args, global),
+        Command::Otp(OtpCommand::Qr(args)) => otp::qr(args, global),
+        Command::Interactive(args) => interactive::run(args, global),
+        Command::Completions(args) => completions(args.shell, &mut io::stdout()),
+        #[cfg(feature = "native")]
+        Command::Master(command) => master::run(command),
+    };
+
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
+}
+
+/// Write the completion script of the `shell`.
+fn completions(shell: Shell, out: &mut dyn Write) -> Result<(), CliError> {
+    clap_complete::generate(shell, &mut Cli::command(), "lesspass-otp", out);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_scripts() {
+        for shell in &[Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut script = Vec::new();
+            completions(*shell, &mut script).unwrap();
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("lesspass-otp"));
+            assert!(script.contains("interactive"));
+        }
+    }
+}
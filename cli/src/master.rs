@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+
+use serde_json::{json, Value};
+
+use zeroize::Zeroizing;
+
+use lesspass_otp::{Algorithm, LessPass};
+
+use crate::args::Global;
+#[cfg(feature = "native")]
+use crate::args::Master;
+use crate::errors::CliError;
+#[cfg(feature = "native")]
+use crate::keychain;
+
+/// Ask the master password, without echoing it.
+///
+/// If the standard input is not a terminal, the master password is its first line, unless
+/// `stdin_used` because it carries other data: the controlling terminal is used instead.
+pub fn prompt(stdin_used: bool) -> Result<Zeroizing<String>, CliError> {
+    let master = if stdin_used || io::stdin().is_terminal() {
+        Zeroizing::new(rpassword::prompt_password("Master password: ")?)
+    } else {
+        let mut master = Zeroizing::new(String::new());
+        io::stdin().lock().read_line(&mut master)?;
+        let len = master.trim_end_matches(&['\r', '\n'][..]).len();
+        master.truncate(len);
+        master
+    };
+
+    if master.is_empty() {
+        return Err(CliError::EmptyMaster);
+    }
+    Ok(master)
+}
+
+/// Get the master password from the OS keychain if asked, or ask it.
+fn master(global: &Global, stdin_used: bool) -> Result<Zeroizing<String>, CliError> {
+    #[cfg(feature = "native")]
+    {
+        if global.keychain {
+            return keychain::load();
+        }
+    }
+    #[cfg(not(feature = "native"))]
+    let _ = global;
+
+    prompt(stdin_used)
+}
+
+/// Format the fingerprint of the master password to be displayed in the terminal.
+pub fn fingerprint(lesspass: &LessPass) -> String {
+    lesspass
+        .get_fingerprint(b"")
+        .iter()
+        .map(|(color, icon)| format!("{} {}", color, icon))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// List the fingerprint of the master password as JSON objects.
+pub fn fingerprint_json(lesspass: &LessPass) -> Value {
+    lesspass
+        .get_fingerprint(b"")
+        .iter()
+        .map(|(color, icon)| json!({ "color": color, "icon": icon }))
+        .collect()
+}
+
+/// Ask the master password, display its fingerprint and, unless `yes`, ask the user to
+/// confirm it before calling `f`.
+///
+/// `stdin_used` if the standard input was read for something else than the master password.
+///
+/// The master password is erased from the memory once `f` returns.
+pub fn with_master<T, F>(global: &Global, yes: bool, stdin_used: bool, f: F) -> Result<T, CliError>
+where
+    F: FnOnce(&LessPass) -> Result<T, CliError>,
+{
+    let master = master(global, stdin_used)?;
+    // The fingerprint depends on the algorithm, the one of the derivation is in the settings
+    let lesspass = LessPass::new(&master, Algorithm::SHA256)?;
+    check_fingerprint(&lesspass, yes, stdin_used)?;
+
+    f(&lesspass)
+}
+
+/// Display the fingerprint and, unless `yes`, ask the user to confirm it.
+fn check_fingerprint(lesspass: &LessPass, yes: bool, stdin_used: bool) -> Result<(), CliError> {
+    eprintln!("Fingerprint: {}", fingerprint(lesspass));
+    if !yes && !confirm("Is the fingerprint correct?", stdin_used)? {
+        return Err(CliError::Aborted);
+    }
+    Ok(())
+}
+
+/// Store the master password in the OS keychain, or remove it.
+#[cfg(feature = "native")]
+pub fn run(command: &Master) -> Result<(), CliError> {
+    match command {
+        Master::Store { yes } => {
+            let master = prompt(false)?;
+            check_fingerprint(&LessPass::new(&master, Algorithm::SHA256)?, *yes, false)?;
+            keychain::store(&master)?;
+            eprintln!("Master password stored in the OS keychain.");
+        }
+        Master::Forget => {
+            keychain::forget()?;
+            eprintln!("Master password removed from the OS keychain.");
+        }
+    }
+    Ok(())
+}
+
+/// Ask a yes/no `question` on the terminal, yes being the default answer.
+///
+/// Like [`prompt`], the answer is read from the controlling terminal if `stdin_used`.
+pub fn confirm(question: &str, stdin_used: bool) -> Result<bool, CliError> {
+    eprint!("{} [Y/n] ", question);
+    io::stderr().flush()?;
+
+    if stdin_used {
+        answer(BufReader::new(terminal()?))
+    } else {
+        answer(io::stdin().lock())
+    }
+}
+
+/// Open the controlling terminal, to read from it while the standard input carries data.
+fn terminal() -> io::Result<File> {
+    #[cfg(windows)]
+    let path = "CONIN$";
+    #[cfg(not(windows))]
+    let path = "/dev/tty";
+    File::open(path)
+}
+
+/// Read the answer to a yes/no question from `input`, yes being the default answer.
+fn answer<R: BufRead>(mut input: R) -> Result<bool, CliError> {
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        // Nobody to answer
+        return Ok(false);
+    }
+    Ok(matches!(answer.trim(), "" | "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_fingerprint() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        assert_eq!(
+            fingerprint(&lesspass),
+            "#FFB5DA fa-flask | #009191 fa-archive | #B5DAFE fa-beer"
+        );
+        assert_eq!(
+            fingerprint_json(&lesspass)[1],
+            json!({ "color": "#009191", "icon": "fa-archive" })
+        );
+    }
+
+    #[test]
+    fn answer_after_piped_secret() {
+        // `echo SEED | lesspass-otp secret encrypt`: the secret exhausts the standard input
+        let mut stdin = io::Cursor::new("SEED\n");
+        let mut secret = String::new();
+        stdin.read_line(&mut secret).unwrap();
+        assert!(!answer(&mut stdin).unwrap());
+
+        // while the terminal still answers
+        assert!(answer(io::Cursor::new("\n")).unwrap());
+        assert!(answer(io::Cursor::new("y\n")).unwrap());
+        assert!(!answer(io::Cursor::new("n\n")).unwrap());
+    }
+}
@@ -0,0 +1,283 @@
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+use std::{fs, thread};
+
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use serde_json::{json, Value};
+
+use lesspass_otp::keyring::Profile;
+use lesspass_otp::{decode_base32, Otp, OtpProvisioning};
+
+use crate::args::{Global, Hotp, OtpAlgorithm, OtpArgs, Qr, Totp};
+use crate::errors::CliError;
+use crate::{master, output, profiles, secret};
+
+/// Display the TOTP token of the current time, or keep displaying it if `watch`.
+pub fn totp(args: &Totp, global: &Global) -> Result<(), CliError> {
+    let loaded = load(&args.otp, global, false, args.period)?;
+    let otp = &loaded.otp;
+
+    if !args.watch {
+        return print_totp(&loaded, args.otp.copy, global);
+    }
+
+    let mut stdout = io::stdout();
+    let mut previous = String::new();
+    loop {
+        let now = now();
+        let token = otp.totp_from_ts(now);
+        if global.json {
+            // One object per token
+            if token != previous {
+                let value = json!({ "token": token, "ttl": remaining(otp, now) });
+                writeln!(stdout, "{}", value)?;
+                previous = token;
+            }
+        } else {
+            write!(stdout, "\r{}  {:>3}s", token, remaining(otp, now))?;
+        }
+        stdout.flush()?;
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// Display the HOTP token of the counter.
+pub fn hotp(args: &Hotp, global: &Global) -> Result<(), CliError> {
+    let loaded = load(&args.otp, global, true, None)?;
+    print_hotp(&loaded, args.counter, args.otp.copy, global)
+}
+
+/// Print or copy the TOTP token of the current time.
+pub fn print_totp(loaded: &Loaded, copy: bool, global: &Global) -> Result<(), CliError> {
+    let now = now();
+    let token = loaded.otp.totp_from_ts(now);
+
+    if global.json {
+        let value = json!({
+            "token": token,
+            "ttl": remaining(&loaded.otp, now),
+            "fingerprint": loaded.fingerprint,
+        });
+        output::json(value, "token", copy)
+    } else {
+        output::secret(&token, copy)
+    }
+}
+
+/// Print or copy the HOTP token of the `counter`.
+pub fn print_hotp(
+    loaded: &Loaded,
+    counter: u64,
+    copy: bool,
+    global: &Global,
+) -> Result<(), CliError> {
+    let token = loaded.otp.hotp(counter);
+
+    if global.json {
+        let value = json!({
+            "token": token,
+            "counter": counter,
+            "fingerprint": loaded.fingerprint,
+        });
+        output::json(value, "token", copy)
+    } else {
+        output::secret(&token, copy)
+    }
+}
+
+/// Print the provisioning QR code in the terminal.
+pub fn qr(args: &Qr, global: &Global) -> Result<(), CliError> {
+    let loaded = load(&args.otp, global, args.counter.is_some(), args.period)?;
+
+    let mut provisioning = loaded
+        .provisioning
+        .unwrap_or_else(|| OtpProvisioning::new(""));
+    if let Some(label) = &args.label {
+        provisioning.set_label(label);
+    }
+    if args.issuer.is_some() {
+        provisioning.set_issuer(args.issuer.as_deref());
+    }
+    if args.counter.is_some() {
+        provisioning.set_counter(args.counter);
+    }
+    // The secret is always known, it has been read or decrypted
+    let uri = provisioning.to_uri(&loaded.otp).unwrap_or_default();
+    let qr = render_qr(&uri)?;
+
+    if global.json {
+        let value = json!({
+            "uri": uri,
+            "qr": qr,
+            "fingerprint": loaded.fingerprint,
+        });
+        return output::json(value, "uri", args.otp.copy);
+    }
+
+    println!("{}", qr);
+    if args.otp.copy {
+        output::secret(&uri, true)?;
+    }
+    Ok(())
+}
+
+/// Render `data` as a QR code made of unicode blocks.
+pub fn render_qr(data: &str) -> Result<String, CliError> {
+    let code = QrCode::new(data).map_err(|error| CliError::QrCode(error.to_string()))?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+/// An [`Otp`] ready to compute the tokens.
+#[derive(Debug)]
+pub struct Loaded {
+    otp: Otp,
+    // Provisioning metadata of the profile
+    provisioning: Option<OtpProvisioning>,
+    // Fingerprint of the master password, if the secret was encrypted
+    fingerprint: Option<Value>,
+}
+
+/// Create the [`Otp`] from the profile, the file or the encrypted secret.
+fn load(
+    args: &OtpArgs,
+    global: &Global,
+    hotp: bool,
+    period: Option<u32>,
+) -> Result<Loaded, CliError> {
+    if let Some(name) = &args.profile {
+        let mut profile = profiles::find(global.profiles.as_deref(), name)?;
+        if let Some(stored) = &mut profile.otp {
+            if let Some(digits) = args.digits {
+                stored.digits = digits;
+            }
+            if let Some(algorithm) = args.algorithm {
+                stored.algorithm = algorithm.into();
+            }
+            if let Some(period) = period {
+                stored.period = period;
+            }
+        }
+        return from_profile(profile, global, args.yes);
+    }
+
+    let (secret, fingerprint) = load_secret(args, global, hotp)?;
+    let otp = Otp::new(
+        &secret,
+        args.digits.unwrap_or(6),
+        Some(args.algorithm.unwrap_or(OtpAlgorithm::Sha1).into()),
+        period,
+        None,
+    )?;
+    Ok(Loaded {
+        otp,
+        provisioning: None,
+        fingerprint,
+    })
+}
+
+/// Decrypt the OTP secret of the `profile` with the master password.
+pub fn from_profile(mut profile: Profile, global: &Global, yes: bool) -> Result<Loaded, CliError> {
+    let stored = profile
+        .otp
+        .take()
+        .ok_or_else(|| CliError::NoOtp(profile.name.clone()))?;
+    let (otp, fingerprint) = master::with_master(global, yes, false, |lesspass| {
+        Ok((
+            stored.decrypt(lesspass, &profile.site, &profile.login)?,
+            master::fingerprint_json(lesspass),
+        ))
+    })?;
+    Ok(Loaded {
+        otp,
+        provisioning: Some(stored.provisioning),
+        fingerprint: Some(fingerprint),
+    })
+}
+
+impl Loaded {
+    /// Provisioning metadata of the profile.
+    pub const fn provisioning(&self) -> Option<&OtpProvisioning> {
+        self.provisioning.as_ref()
+    }
+}
+
+/// Read the clear secret from the file, or decrypt the encrypted one with the master
+/// password.
+fn load_secret(
+    args: &OtpArgs,
+    global: &Global,
+    hotp: bool,
+) -> Result<(Vec<u8>, Option<Value>), CliError> {
+    match (
+        &args.secret_file,
+        &args.encrypted_secret,
+        &args.site,
+        &args.login,
+    ) {
+        (Some(file), _, _, _) => Ok((decode_base32(fs::read_to_string(file)?.trim())?, None)),
+        (None, Some(encrypted), Some(site), Some(login)) => {
+            let encrypted = secret::decode_blob(encrypted)?;
+            master::with_master(global, args.yes, false, |lesspass| {
+                let secret = if hotp {
+                    lesspass.secret_hotp(site, login, &encrypted)?
+                } else {
+                    lesspass.secret_totp(site, login, &encrypted)?
+                };
+                Ok((secret, Some(master::fingerprint_json(lesspass))))
+            })
+        }
+        // Enforced by the arguments parser
+        _ => unreachable!(),
+    }
+}
+
+/// Number of seconds since Unix Epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Number of seconds before the token at `now` expires.
+pub fn remaining(otp: &Otp, now: u64) -> u64 {
+    let period = u64::from(otp.get_period());
+    // The clock may be before the beginning of the TOTP
+    period - now.saturating_sub(otp.get_timestamp()) % period
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qr_code() {
+        let qr = render_qr("otpauth://totp/me?secret=JBSWY3DPEHPK3PXP").unwrap();
+        // Every line of the QR code has the same width
+        let widths = qr
+            .lines()
+            .map(|line| line.chars().count())
+            .collect::<Vec<_>>();
+        assert!(widths.len() > 10);
+        assert!(widths.iter().all(|width| *width == widths[0]));
+    }
+
+    #[test]
+    fn remaining_validity() {
+        let otp = Otp::new(b"12345678901234567890", 6, None, Some(30), None).unwrap();
+        assert_eq!(remaining(&otp, 0), 30);
+        assert_eq!(remaining(&otp, 29), 1);
+        assert_eq!(remaining(&otp, 30), 30);
+        assert_eq!(remaining(&otp, 59), 1);
+
+        let otp = Otp::new(b"12345678901234567890", 6, None, Some(30), Some(100)).unwrap();
+        assert_eq!(remaining(&otp, 129), 1);
+        // Clock behind the beginning of the TOTP
+        assert_eq!(remaining(&otp, 10), 30);
+    }
+}
@@ -0,0 +1,29 @@
+use serde_json::Value;
+
+use crate::errors::CliError;
+
+/// Print the `secret` on the standard output, or copy it to the clipboard if `copy`.
+pub fn secret(secret: &str, copy: bool) -> Result<(), CliError> {
+    if copy {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(secret))
+            .map_err(|error| CliError::Clipboard(error.to_string()))?;
+        eprintln!("Copied to the clipboard.");
+    } else {
+        println!("{}", secret);
+    }
+    Ok(())
+}
+
+/// Print `value` as a JSON object on the standard output.
+///
+/// If `copy`, its `secret` field is copied to the clipboard instead of being printed.
+pub fn json(mut value: Value, secret: &str, copy: bool) -> Result<(), CliError> {
+    if copy {
+        if let Some(Value::String(text)) = value.as_object_mut().and_then(|o| o.remove(secret)) {
+            self::secret(&text, true)?;
+        }
+    }
+    println!("{}", value);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use lesspass_otp::keyring::{Keyring, Profile};
+
+use crate::errors::CliError;
+
+/// Default location of the profiles, `~/.config/lesspass-otp/profiles.toml` on Linux.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("lesspass-otp").join("profiles.toml"))
+}
+
+/// Load the profiles from `path`, or from the default location.
+///
+/// A missing default file is an empty list of profiles, a missing explicit one is an error.
+pub fn load(path: Option<&Path>) -> Result<Keyring, CliError> {
+    let (path, explicit) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Keyring::default()),
+        },
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(content) => parse(&content),
+        Err(error) if error.kind() == ErrorKind::NotFound && !explicit => Ok(Keyring::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Find the profile named `name`.
+pub fn find(path: Option<&Path>, name: &str) -> Result<Profile, CliError> {
+    load(path)?
+        .find(name)
+        .cloned()
+        .ok_or_else(|| CliError::UnknownProfile(name.to_owned()))
+}
+
+/// Parse the TOML content of a profiles file.
+fn parse(content: &str) -> Result<Keyring, CliError> {
+    toml::from_str(content).map_err(|error| CliError::InvalidProfiles(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use lesspass_otp::{Algorithm, LessPass};
+
+    #[test]
+    fn parse_profiles() {
+        let keyring = parse(
+            r#"
+            [[profiles]]
+            name = "lesspass"
+            site = "lesspass.com"
+            login = "contact@lesspass.com"
+            symbols = false
+
+            [[profiles]]
+            name = "github"
+            site = "github.com"
+            login = "me"
+            length = 20
+            algorithm = "SHA512"
+
+            [profiles.otp]
+            secret = "AAEC/f7/"
+            provisioning = { label = "me", issuer = "GitHub" }
+            "#,
+        )
+        .unwrap();
+
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let profile = keyring.find("lesspass").unwrap();
+        assert_eq!(profile.counter, 1);
+        assert!(profile.otp.is_none());
+        assert_eq!(profile.password(&lesspass).unwrap(), "OlfK63bmUhqrGODR");
+
+        let profile = keyring.find("github").unwrap();
+        assert_eq!(profile.length, 20);
+        assert_eq!(profile.algorithm, Some(Algorithm::SHA512));
+        let otp = profile.otp.as_ref().unwrap();
+        assert_eq!(otp.secret, [0, 1, 2, 253, 254, 255]);
+        assert_eq!(otp.digits, 6);
+        assert_eq!(otp.algorithm, Algorithm::SHA1);
+        assert_eq!(otp.period, 30);
+        assert_eq!(otp.provisioning.get_issuer(), Some("GitHub"));
+        assert_eq!(otp.provisioning.get_counter(), None);
+    }
+
+    #[test]
+    fn invalid_profiles() {
+        assert!(matches!(
+            parse("[[profiles]]\nlength = \"long\""),
+            Err(CliError::InvalidProfiles(_))
+        ));
+        assert!(load(Some(Path::new("/nonexistent/profiles.toml"))).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::io::{self, Read};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use lesspass_otp::{decode_base32, LessPassError};
+
+use crate::args::{Global, SecretArgs};
+use crate::errors::CliError;
+use crate::master;
+
+/// Encrypt the base32 encoded secret, and print the base64 encoded blob.
+pub fn encrypt(args: &SecretArgs, global: &Global) -> Result<(), CliError> {
+    let clear = decode_base32(read(args)?.trim())?;
+    // 32 and 64 bytes secrets are considered encrypted by the library
+    if clear.len() == 32 || clear.len() == 64 {
+        return Err(LessPassError::InvalidLength.into());
+    }
+
+    let (encrypted, fingerprint) = convert(args, global, &clear)?;
+    print(encode_blob(&encrypted), fingerprint, global.json);
+    Ok(())
+}
+
+/// Decrypt the base64 encoded blob, and print the base32 encoded secret.
+pub fn decrypt(args: &SecretArgs, global: &Global) -> Result<(), CliError> {
+    let encrypted = decode_blob(&read(args)?)?;
+    if encrypted.len() != 32 && encrypted.len() != 64 {
+        return Err(LessPassError::InvalidLength.into());
+    }
+
+    let (clear, fingerprint) = convert(args, global, &encrypted)?;
+    print(
+        base32::encode(base32::Alphabet::RFC4648 { padding: false }, &clear),
+        fingerprint,
+        global.json,
+    );
+    Ok(())
+}
+
+/// Encode an encrypted secret to store it.
+pub fn encode_blob(encrypted: &[u8]) -> String {
+    STANDARD.encode(encrypted)
+}
+
+/// Decode a stored encrypted secret.
+pub fn decode_blob(encoded: &str) -> Result<Vec<u8>, CliError> {
+    STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| CliError::InvalidBase64)
+}
+
+/// Print the converted `secret`, as JSON with the `fingerprint` if `json`.
+fn print(secret: String, fingerprint: Value, json: bool) {
+    if json {
+        println!(
+            "{}",
+            json!({ "secret": secret, "fingerprint": fingerprint })
+        );
+    } else {
+        println!("{}", secret);
+    }
+}
+
+/// Read the secret from the file, or from the standard input.
+fn read(args: &SecretArgs) -> Result<String, CliError> {
+    Ok(match &args.file {
+        Some(file) => fs::read_to_string(file)?,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    })
+}
+
+/// Encrypt a clear secret, or decrypt an encrypted one, with the fingerprint of the master
+/// password.
+fn convert(
+    args: &SecretArgs,
+    global: &Global,
+    secret: &[u8],
+) -> Result<(Vec<u8>, Value), CliError> {
+    // Without a file, the secret was read from the standard input
+    let stdin_used = args.file.is_none();
+    master::with_master(global, args.yes, stdin_used, |lesspass| {
+        let converted = if args.hotp {
+            lesspass.secret_hotp(&args.site, &args.login, secret)?
+        } else {
+            lesspass.secret_totp(&args.site, &args.login, secret)?
+        };
+        Ok((converted, master::fingerprint_json(lesspass)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_round_trip() {
+        let blob = [0_u8, 1, 2, 253, 254, 255];
+        assert_eq!(encode_blob(&blob), "AAEC/f7/");
+        assert_eq!(decode_blob(" AAEC/f7/\n").unwrap(), blob);
+        assert!(decode_blob("not base64!").is_err());
+    }
+}
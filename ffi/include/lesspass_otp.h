@@ -0,0 +1,74 @@
+/*
+ * C bindings of lesspass-otp.
+ *
+ * Every returned pointer is owned by the caller and must be released with the
+ * matching lp_*free function. On error, the functions return NULL.
+ */
+
+#ifndef LESSPASS_OTP_H
+#define LESSPASS_OTP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Algorithms */
+#define LP_SHA1     0
+#define LP_SHA256   1
+#define LP_SHA384   2
+#define LP_SHA512   3
+#define LP_SHA3_256 4
+#define LP_SHA3_384 5
+#define LP_SHA3_512 6
+
+/* Characters sets, to be combined */
+#define LP_LOWERCASE (1u << 0)
+#define LP_UPPERCASE (1u << 1)
+#define LP_NUMBERS   (1u << 2)
+#define LP_SYMBOLS   (1u << 3)
+
+/* Master password handle */
+typedef struct LpLessPass LpLessPass;
+
+/*
+ * Create a handle from the master password and the algorithm used by default.
+ * Release it with lp_free.
+ */
+LpLessPass *lp_new(const char *master, uint32_t algorithm);
+
+/*
+ * Derive the password of login on site, charsets being a combination of the
+ * LP_LOWERCASE, LP_UPPERCASE, LP_NUMBERS and LP_SYMBOLS flags.
+ * Release it with lp_string_free.
+ */
+char *lp_password(const LpLessPass *lp, const char *site, const char *login,
+                  uint32_t counter, uint8_t length, uint32_t charsets);
+
+/*
+ * Compute the TOTP token at timestamp (seconds from Unix Epoch).
+ * Release it with lp_string_free.
+ */
+char *lp_totp(const uint8_t *secret, size_t secret_len, uint8_t digits,
+              uint32_t algorithm, uint32_t period, uint64_t timestamp);
+
+/*
+ * Get the fingerprint of the master password, formatted as
+ * "color:icon,color:icon,color:icon".
+ * Release it with lp_string_free.
+ */
+char *lp_fingerprint(const LpLessPass *lp);
+
+/* Release a handle returned by lp_new. NULL is ignored. */
+void lp_free(LpLessPass *lp);
+
+/* Release a string returned by this library. NULL is ignored. */
+void lp_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LESSPASS_OTP_H */
@@ -0,0 +1,303 @@
+#![deny(missing_docs)]
+#![deny(missing_copy_implementations)]
+#![deny(missing_debug_implementations)]
+#![deny(trivial_numeric_casts)]
+#![deny(unused_extern_crates)]
+#![deny(unused_qualifications)]
+
+//! C-compatible bindings of `lesspass-otp`.
+//!
+//! The functions are declared in `include/lesspass_otp.h`. Every returned pointer is owned
+//! by the caller, and must be released with the matching `lp_*free` function.
+//!
+//! On error, the functions return a `NULL` pointer.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::{ptr, slice};
+
+use lesspass_otp::charset::{LowerCase, Numbers, Symbols, UpperCase};
+use lesspass_otp::{Algorithm, LessPass, Otp, Settings};
+
+/// Use lowercase letters in the generated password.
+pub const LP_LOWERCASE: u32 = 1;
+/// Use uppercase letters in the generated password.
+pub const LP_UPPERCASE: u32 = 1 << 1;
+/// Use numbers in the generated password.
+pub const LP_NUMBERS: u32 = 1 << 2;
+/// Use symbols in the generated password.
+pub const LP_SYMBOLS: u32 = 1 << 3;
+
+/// Master password handle, created by [`lp_new`] and released by [`lp_free`].
+#[derive(Debug)]
+pub struct LpLessPass {
+    master: String,
+    algorithm: Algorithm,
+}
+
+/// Convert the C `algorithm` value to an [`Algorithm`].
+fn algorithm(algorithm: u32) -> Option<Algorithm> {
+    Some(match algorithm {
+        0 => Algorithm::SHA1,
+        1 => Algorithm::SHA256,
+        2 => Algorithm::SHA384,
+        3 => Algorithm::SHA512,
+        4 => Algorithm::SHA3_256,
+        5 => Algorithm::SHA3_384,
+        6 => Algorithm::SHA3_512,
+        _ => return None,
+    })
+}
+
+/// Borrow a C string as a `&str`, `None` if it is `NULL` or not valid UTF-8.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/// Give the ownership of `s` to the caller, as a C string.
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Create a handle from the `master` password and the `algorithm` used by default.
+///
+/// Return `NULL` if the `master` is not valid UTF-8 or if the `algorithm` is not supported.
+///
+/// # Safety
+///
+/// `master` must be a valid, NUL terminated, C string.
+#[no_mangle]
+pub unsafe extern "C" fn lp_new(master: *const c_char, algorithm: u32) -> *mut LpLessPass {
+    let (master, algorithm) = match (to_str(master), self::algorithm(algorithm)) {
+        (Some(master), Some(algorithm)) => (master, algorithm),
+        _ => return ptr::null_mut(),
+    };
+    // Validate the parameters once
+    if LessPass::new(master, algorithm).is_err() {
+        return ptr::null_mut();
+    }
+
+    Box::into_raw(Box::new(LpLessPass {
+        master: master.to_owned(),
+        algorithm,
+    }))
+}
+
+/// Derive the password of `login` on `site`.
+///
+/// `charsets` is a combination of the `LP_LOWERCASE`, `LP_UPPERCASE`, `LP_NUMBERS` and
+/// `LP_SYMBOLS` flags.
+///
+/// Return `NULL` if a parameter is not valid. The result must be released with
+/// [`lp_string_free`].
+///
+/// # Safety
+///
+/// `lp` must be a handle returned by [`lp_new`], `site` and `login` must be valid, NUL
+/// terminated, C strings.
+#[no_mangle]
+pub unsafe extern "C" fn lp_password(
+    lp: *const LpLessPass,
+    site: *const c_char,
+    login: *const c_char,
+    counter: u32,
+    length: u8,
+    charsets: u32,
+) -> *mut c_char {
+    let (lp, site, login) = match (lp.as_ref(), to_str(site), to_str(login)) {
+        (Some(lp), Some(site), Some(login)) => (lp, site, login),
+        _ => return ptr::null_mut(),
+    };
+    let lesspass = match LessPass::new(&lp.master, lp.algorithm) {
+        Ok(lesspass) => lesspass,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let settings = Settings::new(
+        length,
+        if charsets & LP_LOWERCASE == 0 {
+            LowerCase::NotUsing
+        } else {
+            LowerCase::Using
+        },
+        if charsets & LP_UPPERCASE == 0 {
+            UpperCase::NotUsing
+        } else {
+            UpperCase::Using
+        },
+        if charsets & LP_NUMBERS == 0 {
+            Numbers::NotUsing
+        } else {
+            Numbers::Using
+        },
+        if charsets & LP_SYMBOLS == 0 {
+            Symbols::NotUsing
+        } else {
+            Symbols::Using
+        },
+    );
+
+    match lesspass.password(site, login, counter, &settings) {
+        Ok(password) => into_c_string(password),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Compute the TOTP token at `timestamp` (seconds from Unix Epoch).
+///
+/// Return `NULL` if a parameter is not valid. The result must be released with
+/// [`lp_string_free`].
+///
+/// # Safety
+///
+/// `secret` must point to `secret_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lp_totp(
+    secret: *const u8,
+    secret_len: usize,
+    digits: u8,
+    algorithm: u32,
+    period: u32,
+    timestamp: u64,
+) -> *mut c_char {
+    if secret.is_null() {
+        return ptr::null_mut();
+    }
+    let secret = slice::from_raw_parts(secret, secret_len);
+    let algorithm = match self::algorithm(algorithm) {
+        Some(algorithm) => algorithm,
+        None => return ptr::null_mut(),
+    };
+
+    match Otp::new(secret, digits, Some(algorithm), Some(period), None) {
+        Ok(otp) => into_c_string(otp.totp_from_ts(timestamp)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Get the fingerprint of the master password, to be displayed to the user.
+///
+/// The result is formatted as `color:icon,color:icon,color:icon`, for example
+/// `#FFB5DA:fa-flask,#009191:fa-archive,#B5DAFE:fa-beer`, and must be released with
+/// [`lp_string_free`].
+///
+/// # Safety
+///
+/// `lp` must be a handle returned by [`lp_new`].
+#[no_mangle]
+pub unsafe extern "C" fn lp_fingerprint(lp: *const LpLessPass) -> *mut c_char {
+    let lp = match lp.as_ref() {
+        Some(lp) => lp,
+        None => return ptr::null_mut(),
+    };
+    let lesspass = match LessPass::new(&lp.master, lp.algorithm) {
+        Ok(lesspass) => lesspass,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let fingerprint = lesspass
+        .get_fingerprint(b"")
+        .iter()
+        .map(|(color, icon)| format!("{}:{}", color, icon))
+        .collect::<Vec<_>>()
+        .join(",");
+    into_c_string(fingerprint)
+}
+
+/// Release a handle returned by [`lp_new`]. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `lp` must be a handle returned by [`lp_new`], not already released.
+#[no_mangle]
+pub unsafe extern "C" fn lp_free(lp: *mut LpLessPass) {
+    if !lp.is_null() {
+        drop(Box::from_raw(lp));
+    }
+}
+
+/// Release a string returned by this library. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `s` must be a string returned by this library, not already released.
+#[no_mangle]
+pub unsafe extern "C" fn lp_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        lp_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn password_and_fingerprint() {
+        let master = CString::new("password").unwrap();
+        let site = CString::new("lesspass.com").unwrap();
+        let login = CString::new("contact@lesspass.com").unwrap();
+        unsafe {
+            let lp = lp_new(master.as_ptr(), 1);
+            assert!(!lp.is_null());
+
+            let password = lp_password(
+                lp,
+                site.as_ptr(),
+                login.as_ptr(),
+                1,
+                16,
+                LP_LOWERCASE | LP_UPPERCASE | LP_NUMBERS,
+            );
+            assert_eq!(take(password), "OlfK63bmUhqrGODR");
+
+            assert_eq!(
+                take(lp_fingerprint(lp)),
+                "#FFB5DA:fa-flask,#009191:fa-archive,#B5DAFE:fa-beer"
+            );
+
+            // No charset
+            assert!(lp_password(lp, site.as_ptr(), login.as_ptr(), 1, 16, 0).is_null());
+
+            lp_free(lp);
+        }
+    }
+
+    #[test]
+    fn invalid_handle() {
+        let master = CString::new("password").unwrap();
+        unsafe {
+            // SHA1 is not supported by LessPass
+            assert!(lp_new(master.as_ptr(), 0).is_null());
+            assert!(lp_new(master.as_ptr(), 42).is_null());
+            assert!(lp_new(ptr::null(), 1).is_null());
+            assert!(lp_fingerprint(ptr::null()).is_null());
+            lp_free(ptr::null_mut());
+            lp_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn totp() {
+        let seed = b"12345678901234567890";
+        unsafe {
+            assert_eq!(
+                take(lp_totp(seed.as_ptr(), seed.len(), 8, 0, 30, 59)),
+                "94287082"
+            );
+            assert!(lp_totp(seed.as_ptr(), seed.len(), 4, 0, 30, 59).is_null());
+            assert!(lp_totp(ptr::null(), 0, 6, 0, 30, 59).is_null());
+        }
+    }
+}
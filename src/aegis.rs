@@ -0,0 +1,349 @@
+use core::convert::TryInto;
+use core::fmt::Write;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, OsRng};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::LessPassError;
+
+/// Type of the slots whose key is derived from a password.
+const PASSWORD_SLOT: u8 = 1;
+/// Length of the AES-GCM tag, appended to the cipher text.
+const TAG_LEN: usize = 16;
+/// Cost of scrypt used by Aegis for the new vaults: N, r and p.
+const SCRYPT_COST: (u64, u32, u32) = (1 << 15, 8, 1);
+/// Highest cost of scrypt accepted from a vault, a bit above the one of Aegis: higher ones
+/// would exhaust the memory or the CPU.
+const MAX_SCRYPT_COST: (u64, u32, u32) = (1 << 17, 8, 1);
+/// Highest number of password slots tried, each one costing a derivation.
+const MAX_PASSWORD_SLOTS: usize = 4;
+
+/// A vault of Aegis Authenticator, as exported in JSON.
+#[derive(Serialize, Deserialize)]
+pub struct Vault {
+    pub version: u32,
+    pub header: Header,
+    /// The [`Database`], encrypted and base64 encoded if the vault has slots.
+    pub db: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Header {
+    pub slots: Option<Vec<Slot>>,
+    pub params: Option<KeyParams>,
+}
+
+/// A slot holds the master key of the vault, encrypted with a key derived from a password
+/// or stored in the Android keystore.
+#[derive(Serialize, Deserialize)]
+pub struct Slot {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    #[serde(default)]
+    pub uuid: String,
+    pub key: String,
+    pub key_params: KeyParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+}
+
+/// Nonce and tag of an AES-GCM encryption, hexadecimal encoded.
+#[derive(Serialize, Deserialize)]
+pub struct KeyParams {
+    pub nonce: String,
+    pub tag: String,
+}
+
+/// The decrypted content of a [`Vault`].
+#[derive(Serialize, Deserialize)]
+pub struct Database {
+    pub version: u32,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    /// `totp` or `hotp`, or a proprietary OTP like `steam`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub uuid: String,
+    pub name: String,
+    #[serde(default)]
+    pub issuer: String,
+    #[serde(default)]
+    pub note: String,
+    pub info: Info,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Info {
+    /// Secret, base32 encoded.
+    pub secret: String,
+    pub algo: String,
+    pub digits: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u64>,
+}
+
+impl Vault {
+    /// Read the database of the vault in `json`, decrypting it with `password` if needed.
+    pub fn open(json: &str, password: &str) -> Result<Database, LessPassError> {
+        let vault: Self = serde_json::from_str(json).map_err(|_| LessPassError::InvalidBackup)?;
+        let db = match (vault.header.slots, vault.header.params, vault.db) {
+            // Exported without encryption
+            (None, None, db @ Value::Object(_)) => db,
+            (Some(slots), Some(params), Value::String(db)) => {
+                let slots = slots
+                    .iter()
+                    .filter(|slot| slot.kind == PASSWORD_SLOT)
+                    .collect::<Vec<_>>();
+                // The vault is untrusted, its cost must not exhaust the memory nor the CPU
+                if slots.len() > MAX_PASSWORD_SLOTS || !slots.iter().all(|slot| slot.sane_cost()) {
+                    return Err(LessPassError::InvalidBackup);
+                }
+                let master = slots
+                    .iter()
+                    .find_map(|slot| slot.master_key(password))
+                    .ok_or(LessPassError::DecryptionFailed)?;
+                let db = STANDARD
+                    .decode(db.trim())
+                    .map_err(|_| LessPassError::InvalidBackup)?;
+                let db = decrypt(&master, &params, &db).ok_or(LessPassError::DecryptionFailed)?;
+                serde_json::from_slice(&db).map_err(|_| LessPassError::InvalidBackup)?
+            }
+            _ => return Err(LessPassError::InvalidBackup),
+        };
+        serde_json::from_value(db).map_err(|_| LessPassError::InvalidBackup)
+    }
+
+    /// Encrypt the `db` in a vault, whose master key is only stored in a slot of the
+    /// `password`.
+    ///
+    /// A [`LessPassError::EncryptionFailed`] if the vault cannot be encrypted.
+    pub fn seal(db: &Database, password: &str) -> Result<String, LessPassError> {
+        let (n, r, p) = SCRYPT_COST;
+        let salt = random(32);
+        let key = derive(password, &salt, n, r, p).ok_or(LessPassError::EncryptionFailed)?;
+        let master = random(32);
+
+        let (encrypted_master, key_params) = encrypt(&key, &master)?;
+        let slot = Slot {
+            kind: PASSWORD_SLOT,
+            uuid: uuid(),
+            key: hex_encode(&encrypted_master),
+            key_params,
+            n: Some(n),
+            r: Some(r),
+            p: Some(p),
+            salt: Some(hex_encode(&salt)),
+        };
+
+        let db = serde_json::to_vec(db).map_err(|_| LessPassError::InvalidBackup)?;
+        let (db, params) = encrypt(&master, &db)?;
+        let vault = Self {
+            version: 1,
+            header: Header {
+                slots: Some(vec![slot]),
+                params: Some(params),
+            },
+            db: Value::String(STANDARD.encode(db)),
+        };
+        serde_json::to_string_pretty(&vault).map_err(|_| LessPassError::InvalidBackup)
+    }
+}
+
+impl Slot {
+    /// Whether the cost of scrypt is not above [`MAX_SCRYPT_COST`].
+    fn sane_cost(&self) -> bool {
+        let (n, r, p) = MAX_SCRYPT_COST;
+        self.n.is_some_and(|cost| cost <= n)
+            && self.r.is_some_and(|cost| cost <= r)
+            && self.p.is_some_and(|cost| cost <= p)
+    }
+
+    /// Decrypt the master key of the vault, `None` if the `password` is not the one of the
+    /// slot.
+    fn master_key(&self, password: &str) -> Option<Vec<u8>> {
+        let key = derive(
+            password,
+            &hex_decode(self.salt.as_ref()?)?,
+            self.n?,
+            self.r?,
+            self.p?,
+        )?;
+        decrypt(&key, &self.key_params, &hex_decode(&self.key)?)
+    }
+}
+
+/// Derive the key of a password slot with scrypt.
+fn derive(password: &str, salt: &[u8], n: u64, r: u32, p: u32) -> Option<Vec<u8>> {
+    if !n.is_power_of_two() {
+        return None;
+    }
+    let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, 32).ok()?;
+    let mut key = vec![0; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key).ok()?;
+    Some(key)
+}
+
+/// Decrypt the `data` with AES-256-GCM, `None` if the `key` is wrong.
+fn decrypt(key: &[u8], params: &KeyParams, data: &[u8]) -> Option<Vec<u8>> {
+    let nonce: [u8; 12] = hex_decode(&params.nonce)?.try_into().ok()?;
+    // The tag is stored apart, but expected after the cipher text
+    let mut data = data.to_vec();
+    data.extend(hex_decode(&params.tag)?);
+
+    Aes256Gcm::new_from_slice(key)
+        .ok()?
+        .decrypt(&Nonce::from(nonce), data.as_ref())
+        .ok()
+}
+
+/// Encrypt the `data` with AES-256-GCM and a random nonce, the tag being returned apart.
+fn encrypt(key: &[u8], data: &[u8]) -> Result<(Vec<u8>, KeyParams), LessPassError> {
+    let mut nonce = [0; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let mut encrypted = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| LessPassError::EncryptionFailed)?
+        .encrypt(&Nonce::from(nonce), data)
+        .map_err(|_| LessPassError::EncryptionFailed)?;
+    let tag = encrypted.split_off(encrypted.len() - TAG_LEN);
+
+    let params = KeyParams {
+        nonce: hex_encode(&nonce),
+        tag: hex_encode(&tag),
+    };
+    Ok((encrypted, params))
+}
+
+fn random(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// A random version 4 UUID.
+pub fn uuid() -> String {
+    let mut bytes = random(16);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex_encode(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        // Writing to a String cannot fail
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex() {
+        assert_eq!(hex_decode("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("zz"), None);
+        assert_eq!(hex_encode(&[0x00, 0xff, 0x7a]), "00ff7a");
+
+        let uuid = uuid();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+    }
+
+    #[test]
+    fn plain_vault() {
+        let json = r#"{"version": 1, "header": {"slots": null, "params": null},
+            "db": {"version": 2, "entries": [{"type": "totp", "name": "me",
+            "info": {"secret": "JBSWY3DPEHPK3PXP", "algo": "SHA1", "digits": 6, "period": 30}}]}}"#;
+        let db = Vault::open(json, "").unwrap();
+        assert_eq!(db.entries[0].name, "me");
+        assert_eq!(db.entries[0].info.period, Some(30));
+
+        assert_eq!(
+            Vault::open("{\"version\": 1}", "").err(),
+            Some(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn excessive_cost() {
+        let json = r#"{"version": 1, "header": {"slots": [{"type": 1, "key": "00",
+            "key_params": {"nonce": "00", "tag": "00"}, "n": 1099511627776, "r": 1048576,
+            "p": 1, "salt": "00"}], "params": {"nonce": "00", "tag": "00"}}, "db": "AA=="}"#;
+        assert_eq!(
+            Vault::open(json, "").err(),
+            Some(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn cost_checked_before_derivation() {
+        let slot = |n: u64| {
+            format!(
+                r#"{{"type": 1, "key": "00", "key_params": {{"nonce": "00", "tag": "00"}},
+                "n": {}, "r": 8, "p": 1, "salt": "00"}}"#,
+                n
+            )
+        };
+        let vault = |slots: &[String]| {
+            format!(
+                r#"{{"version": 1, "header": {{"slots": [{}],
+                "params": {{"nonce": "00", "tag": "00"}}}}, "db": "AA=="}}"#,
+                slots.join(",")
+            )
+        };
+
+        // Cheap slots are derived, but none is the one of the password
+        assert_eq!(
+            Vault::open(&vault(&[slot(2)]), "").err(),
+            Some(LessPassError::DecryptionFailed)
+        );
+        // A slot over the cap rejects the vault, even after a cheap slot
+        assert_eq!(
+            Vault::open(&vault(&[slot(2), slot(1 << 18)]), "").err(),
+            Some(LessPassError::InvalidBackup)
+        );
+        // As too many slots
+        assert_eq!(
+            Vault::open(&vault(&vec![slot(2); MAX_PASSWORD_SLOTS + 1]), "").err(),
+            Some(LessPassError::InvalidBackup)
+        );
+    }
+}
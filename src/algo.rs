@@ -10,6 +10,7 @@ use sha3::{Sha3_256, Sha3_384, Sha3_512};
 
 /// Selects the hash algorithm to use in PBKDF or HMAC.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Algorithm {
     /// SHA1.
     ///
@@ -90,8 +91,7 @@ impl Algorithm {
                 // Length of the output array, based on $hash specified
                 let len = <$hash as FixedOutput>::OutputSize::to_usize();
                 // Initialize an array of the specific length
-                let mut hex = Vec::with_capacity(len);
-                unsafe { hex.set_len(len) };
+                let mut hex = vec![0_u8; len];
                 // Compute the PBKDF2, based on the selected $hash
                 pbkdf2_::<Hmac<$hash>>(key, data, iterations, &mut hex.as_mut_slice());
                 // Return the array
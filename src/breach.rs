@@ -0,0 +1,99 @@
+use core::fmt::Write;
+
+use sha1::{Digest, Sha1};
+
+/// Base URL of the Have I Been Pwned range API.
+pub const HIBP_RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+
+/// Split the SHA-1 hash of the `password` for a k-anonymity query: the prefix, the first 5
+/// hexadecimal characters, is sent to the range API, which answers with the suffixes of
+/// every breached hash sharing it.
+///
+/// The password and its full hash never leave the device.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::breach::hibp_prefix;
+///
+/// let (prefix, suffix) = hibp_prefix("password");
+/// assert_eq!(prefix, "5BAA6");
+/// assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+/// ```
+#[must_use]
+pub fn hibp_prefix(password: &str) -> (String, String) {
+    let mut hash = String::with_capacity(40);
+    for byte in Sha1::digest(password.as_bytes()) {
+        // Writing to a String cannot fail
+        let _ = write!(hash, "{:02X}", byte);
+    }
+    let suffix = hash.split_off(5);
+    (hash, suffix)
+}
+
+/// Number of breaches of the `suffix` listed in the `range`, the body of a range API
+/// response: one `SUFFIX:COUNT` per line.
+#[must_use]
+pub fn hibp_count(range: &str, suffix: &str) -> u64 {
+    range
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().splitn(2, ':');
+            Some((parts.next()?, parts.next()?))
+        })
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Query the Have I Been Pwned range API, and return the number of breaches the
+/// `password` appears in, 0 if it has never been seen.
+///
+/// The response is padded with fake suffixes, so its size does not leak the prefix either.
+///
+/// # Errors
+///
+/// Any error of the request, or an unsuccessful HTTP status.
+#[cfg(feature = "hibp")]
+pub async fn hibp_check(password: &str) -> Result<u64, reqwest::Error> {
+    let (prefix, suffix) = hibp_prefix(password);
+    let range = reqwest::Client::new()
+        .get(format!("{}{}", HIBP_RANGE_API, prefix))
+        .header("Add-Padding", "true")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(hibp_count(&range, &suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_and_suffix() {
+        let (prefix, suffix) = hibp_prefix("");
+        assert_eq!(prefix, "DA39A");
+        assert_eq!(suffix, "3EE5E6B4B0D3255BFEF95601890AFD80709");
+    }
+
+    #[test]
+    fn count_in_range() {
+        let range = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                     1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n\
+                     011053FD0102E94D6AE2F8B83D76FAF94F6:0\r\n";
+        assert_eq!(
+            hibp_count(range, "1E4C9B93F3F0682250B6CF8331B7EE68FD8"),
+            9_659_365
+        );
+        assert_eq!(
+            hibp_count(range, "1e4c9b93f3f0682250b6cf8331b7ee68fd8"),
+            9_659_365
+        );
+        assert_eq!(hibp_count(range, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), 0);
+        // Padding
+        assert_eq!(hibp_count(range, "011053FD0102E94D6AE2F8B83D76FAF94F6"), 0);
+    }
+}
@@ -0,0 +1,575 @@
+use std::convert::TryFrom;
+use std::{error, fmt};
+
+use reqwest::blocking::{Client as HttpClient, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::keyring::Profile;
+
+/// Base URL of the official LessPass Database.
+pub const LESSPASS_API: &str = "https://api.lesspass.com";
+
+/// Most pages of profiles listed, a server paginating more is looping.
+const MAX_PAGES: usize = 1000;
+
+/// Errors that can be returned by the [`Client`].
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent, or the response could not be decoded.
+    Http(reqwest::Error),
+
+    /// The server answered with this unsuccessful HTTP status.
+    Status(u16),
+
+    /// The request requires to [`Client::login`] first.
+    NotLoggedIn,
+
+    /// The profile to update has never been created on the server.
+    MissingId,
+
+    /// The server paginated to another server, or endlessly.
+    InvalidPage,
+
+    /// The profile is of this version of LessPass, not the version 2 implemented here.
+    UnsupportedVersion(u8),
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Http(error)
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(error) => write!(f, "The request to the server failed: {}", error),
+            Self::Status(status) => write!(f, "The server answered with status {}.", status),
+            Self::NotLoggedIn => f.write_str("Not logged in to the server."),
+            Self::MissingId => f.write_str("The profile has no identifier on the server."),
+            Self::InvalidPage => f.write_str("The server returned an invalid next page."),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "The profile is of the version {} of LessPass, only the version 2 is supported.",
+                version
+            ),
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+/// A password profile, as stored by the LessPass Database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteProfile {
+    /// Identifier on the server, `None` until it is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Site of the password.
+    pub site: String,
+    /// Login on the site.
+    pub login: String,
+    /// Use lowercase letters.
+    pub lowercase: bool,
+    /// Use uppercase letters.
+    pub uppercase: bool,
+    /// Use numbers.
+    pub numbers: bool,
+    /// Use symbols.
+    pub symbols: bool,
+    /// Length of the password.
+    pub length: u8,
+    /// Counter, to increment to change the password.
+    pub counter: u32,
+    /// Version of the LessPass algorithm, this crate implements the version 2.
+    #[serde(default = "default_version")]
+    pub version: u8,
+}
+
+const fn default_version() -> u8 {
+    2
+}
+
+impl From<&Profile> for RemoteProfile {
+    fn from(profile: &Profile) -> Self {
+        Self {
+            id: profile.remote_id.clone(),
+            site: profile.site.clone(),
+            login: profile.login.clone(),
+            lowercase: profile.lowercase,
+            uppercase: profile.uppercase,
+            numbers: profile.numbers,
+            symbols: profile.symbols,
+            length: profile.length,
+            counter: profile.counter,
+            version: default_version(),
+        }
+    }
+}
+
+impl TryFrom<&RemoteProfile> for Profile {
+    type Error = ClientError;
+
+    /// The profile is named after its site, and keeps its identifier to be updated later.
+    ///
+    /// # Errors
+    ///
+    /// A [`ClientError::UnsupportedVersion`] if the profile is not of the version 2 of
+    /// LessPass: its password would not be the same.
+    fn try_from(remote: &RemoteProfile) -> Result<Self, ClientError> {
+        if remote.version != default_version() {
+            return Err(ClientError::UnsupportedVersion(remote.version));
+        }
+        Ok(Self {
+            name: remote.site.clone(),
+            site: remote.site.clone(),
+            login: remote.login.clone(),
+            counter: remote.counter,
+            length: remote.length,
+            lowercase: remote.lowercase,
+            uppercase: remote.uppercase,
+            numbers: remote.numbers,
+            symbols: remote.symbols,
+            remote_id: remote.id.clone(),
+            ..Self::default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Tokens {
+    access: String,
+    #[serde(default)]
+    refresh: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Page {
+    // Django REST framework pagination
+    Paginated {
+        results: Vec<RemoteProfile>,
+        next: Option<String>,
+    },
+    // Old servers return every profile at once
+    List(Vec<RemoteProfile>),
+}
+
+/// Client of the REST API of a LessPass Database, the official one or a self-hosted one.
+///
+/// # Examples
+///
+/// ```no_run
+/// use lesspass_otp::client::{Client, LESSPASS_API};
+///
+/// let mut client = Client::new(LESSPASS_API);
+/// client.login("me@example.com", "password of the account")?;
+/// for profile in client.list_profiles()? {
+///     println!("{} on {}", profile.login, profile.site);
+/// }
+///
+/// # Ok::<(), lesspass_otp::client::ClientError>(())
+/// ```
+pub struct Client {
+    base_url: String,
+    http: HttpClient,
+    // JSON Web Tokens
+    access: Option<String>,
+    refresh: Option<String>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the tokens
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("logged_in", &self.access.is_some())
+            .finish()
+    }
+}
+
+impl Client {
+    /// Create a client of the server at `base_url`, like [`LESSPASS_API`].
+    #[must_use]
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            http: HttpClient::new(),
+            access: None,
+            refresh: None,
+        }
+    }
+
+    /// Get the access token, to be stored between sessions.
+    #[must_use]
+    pub fn get_access_token(&self) -> Option<&str> {
+        self.access.as_deref()
+    }
+
+    /// Get the refresh token, to be stored between sessions.
+    #[must_use]
+    pub fn get_refresh_token(&self) -> Option<&str> {
+        self.refresh.as_deref()
+    }
+
+    /// Restore the tokens of a previous session.
+    pub fn set_tokens(&mut self, access: Option<&str>, refresh: Option<&str>) {
+        self.access = access.map(str::to_owned);
+        self.refresh = refresh.map(str::to_owned);
+    }
+
+    /// Log in with the `email` and the `password` of the account.
+    ///
+    /// ## Notes
+    ///
+    /// The official clients do not send the master password, but the password derived
+    /// from it for the site `lesspass.com` and the login `email`.
+    ///
+    /// # Errors
+    ///
+    /// A [`ClientError::Status`] if the credentials are refused.
+    pub fn login(&mut self, email: &str, password: &str) -> Result<(), ClientError> {
+        let request = self
+            .http
+            .post(self.url("/api/auth/jwt/create/"))
+            .json(&serde_json::json!({ "email": email, "password": password }));
+        let tokens: Tokens = check(request.send()?)?.json()?;
+        self.access = Some(tokens.access);
+        self.refresh = tokens.refresh;
+        Ok(())
+    }
+
+    /// Get a new access token with the refresh token, when the access one expired.
+    ///
+    /// # Errors
+    ///
+    /// * [`ClientError::NotLoggedIn`] if there is no refresh token.
+    /// * [`ClientError::Status`] if the refresh token expired too.
+    pub fn refresh(&mut self) -> Result<(), ClientError> {
+        let refresh = self.refresh.as_deref().ok_or(ClientError::NotLoggedIn)?;
+        let request = self
+            .http
+            .post(self.url("/api/auth/jwt/refresh/"))
+            .json(&serde_json::json!({ "refresh": refresh }));
+        let tokens: Tokens = check(request.send()?)?.json()?;
+        self.access = Some(tokens.access);
+        if tokens.refresh.is_some() {
+            self.refresh = tokens.refresh;
+        }
+        Ok(())
+    }
+
+    /// List every profile of the account.
+    ///
+    /// The next pages are only requested on the same server, so the access token is never
+    /// sent elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// * [`ClientError::NotLoggedIn`] if there is no access token.
+    /// * [`ClientError::Status`] if the request is refused.
+    /// * [`ClientError::InvalidPage`] if a next page is on another server, has already been
+    ///   listed, or if there are more than 1000 pages.
+    pub fn list_profiles(&self) -> Result<Vec<RemoteProfile>, ClientError> {
+        let mut profiles = Vec::new();
+        let mut pages = Vec::new();
+        let mut url = Some(self.url("/api/passwords/"));
+        while let Some(page) = url.take() {
+            if pages.len() == MAX_PAGES || pages.contains(&page) {
+                return Err(ClientError::InvalidPage);
+            }
+            let request = self.authorized(self.http.get(&page))?;
+            match check(request.send()?)?.json()? {
+                Page::Paginated { results, next } => {
+                    profiles.extend(results);
+                    url = match next {
+                        Some(next) if !next.starts_with(&self.url("/")) => {
+                            return Err(ClientError::InvalidPage)
+                        }
+                        next => next,
+                    };
+                }
+                Page::List(results) => profiles.extend(results),
+            }
+            pages.push(page);
+        }
+        Ok(profiles)
+    }
+
+    /// Create the `profile` on the server, returning it with its identifier.
+    ///
+    /// # Errors
+    ///
+    /// * [`ClientError::NotLoggedIn`] if there is no access token.
+    /// * [`ClientError::Status`] if the request is refused.
+    pub fn create_profile(&self, profile: &RemoteProfile) -> Result<RemoteProfile, ClientError> {
+        let request = self.authorized(self.http.post(self.url("/api/passwords/")))?;
+        Ok(check(request.json(profile).send()?)?.json()?)
+    }
+
+    /// Replace the `profile` on the server.
+    ///
+    /// # Errors
+    ///
+    /// * [`ClientError::MissingId`] if the profile has no identifier.
+    /// * [`ClientError::NotLoggedIn`] if there is no access token.
+    /// * [`ClientError::Status`] if the request is refused.
+    pub fn update_profile(&self, profile: &RemoteProfile) -> Result<RemoteProfile, ClientError> {
+        let id = profile.id.as_deref().ok_or(ClientError::MissingId)?;
+        let url = self.url(&format!("/api/passwords/{}/", id));
+        let request = self.authorized(self.http.put(url))?;
+        Ok(check(request.json(profile).send()?)?.json()?)
+    }
+
+    /// Delete the profile identified by `id` from the server.
+    ///
+    /// # Errors
+    ///
+    /// * [`ClientError::NotLoggedIn`] if there is no access token.
+    /// * [`ClientError::Status`] if the request is refused.
+    pub fn delete_profile(&self, id: &str) -> Result<(), ClientError> {
+        let url = self.url(&format!("/api/passwords/{}/", id));
+        let request = self.authorized(self.http.delete(url))?;
+        check(request.send()?)?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> Result<RequestBuilder, ClientError> {
+        let access = self.access.as_deref().ok_or(ClientError::NotLoggedIn)?;
+        Ok(request.bearer_auth(access))
+    }
+}
+
+/// Turn an unsuccessful response into an error.
+fn check(response: Response) -> Result<Response, ClientError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(ClientError::Status(response.status().as_u16()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Serve the LessPass Database API on a random local port, recording the requests.
+    fn server() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = Arc::clone(&requests);
+        let next = format!("{}/api/passwords/?page=2", base_url);
+        let base = base_url.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let request_line = head.lines().next().unwrap().to_owned();
+                let (status, response) = match request_line.as_str() {
+                    "POST /api/auth/jwt/create/ HTTP/1.1" => {
+                        ("200 OK", r#"{"access":"a1","refresh":"r1"}"#.to_owned())
+                    }
+                    _ if !head.contains("authorization: Bearer a1") => {
+                        ("401 Unauthorized", "{}".to_owned())
+                    }
+                    "GET /api/passwords/ HTTP/1.1" => (
+                        "200 OK",
+                        format!(
+                            r#"{{"count":2,"next":"{}","previous":null,"results":[{{"id":"1","site":"a.com","login":"me","lowercase":true,"uppercase":true,"numbers":true,"symbols":false,"length":16,"counter":1,"version":2}}]}}"#,
+                            next
+                        ),
+                    ),
+                    "GET /api/passwords/?page=2 HTTP/1.1" => (
+                        "200 OK",
+                        r#"{"count":2,"next":null,"previous":null,"results":[{"id":"2","site":"b.com","login":"me","lowercase":true,"uppercase":false,"numbers":true,"symbols":true,"length":20,"counter":3,"version":2}]}"#.to_owned(),
+                    ),
+                    "GET /foreign/api/passwords/ HTTP/1.1" => (
+                        "200 OK",
+                        r#"{"count":2,"next":"http://127.0.0.1:1/foreign/api/passwords/?page=2","previous":null,"results":[]}"#.to_owned(),
+                    ),
+                    "GET /loop/api/passwords/ HTTP/1.1" => (
+                        "200 OK",
+                        format!(
+                            r#"{{"count":2,"next":"{}/loop/api/passwords/","previous":null,"results":[]}}"#,
+                            base
+                        ),
+                    ),
+                    "POST /api/passwords/ HTTP/1.1" => {
+                        let mut created: RemoteProfile = serde_json::from_slice(&body).unwrap();
+                        created.id = Some("3".to_owned());
+                        ("201 Created", serde_json::to_string(&created).unwrap())
+                    }
+                    "DELETE /api/passwords/3/ HTTP/1.1" => ("204 No Content", String::new()),
+                    _ => ("404 Not Found", "{}".to_owned()),
+                };
+                recorded.lock().unwrap().push(request_line);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        (base_url, requests)
+    }
+
+    #[test]
+    fn profile_conversion() {
+        let mut profile = Profile::new("github", "github.com", "me");
+        profile.symbols = false;
+        profile.counter = 2;
+
+        let remote = RemoteProfile::from(&profile);
+        assert_eq!(remote.id, None);
+        assert_eq!(remote.version, 2);
+        assert!(!remote.symbols);
+        assert_eq!(
+            serde_json::to_value(&remote).unwrap(),
+            serde_json::json!({
+                "site": "github.com",
+                "login": "me",
+                "lowercase": true,
+                "uppercase": true,
+                "numbers": true,
+                "symbols": false,
+                "length": 16,
+                "counter": 2,
+                "version": 2,
+            })
+        );
+
+        let back = Profile::try_from(&remote).unwrap();
+        assert_eq!(back.name, "github.com");
+        assert_eq!(back.counter, 2);
+        assert!(!back.symbols);
+
+        // Pulled profiles are updated, not created again, once pushed back
+        let pulled = RemoteProfile {
+            id: Some("5".to_owned()),
+            ..remote.clone()
+        };
+        let profile = Profile::try_from(&pulled).unwrap();
+        assert_eq!(profile.remote_id.as_deref(), Some("5"));
+        assert_eq!(RemoteProfile::from(&profile), pulled);
+
+        // The passwords of LessPass v1 are generated differently
+        let v1 = RemoteProfile {
+            version: 1,
+            ..remote
+        };
+        assert!(matches!(
+            Profile::try_from(&v1),
+            Err(ClientError::UnsupportedVersion(1))
+        ));
+    }
+
+    #[test]
+    fn api_requests() {
+        let (base_url, requests) = server();
+        let mut client = Client::new(&format!("{}/", base_url));
+
+        assert!(matches!(
+            client.list_profiles(),
+            Err(ClientError::NotLoggedIn)
+        ));
+        assert!(matches!(client.refresh(), Err(ClientError::NotLoggedIn)));
+
+        client.login("me@example.com", "secret").unwrap();
+        assert_eq!(client.get_access_token(), Some("a1"));
+        assert_eq!(client.get_refresh_token(), Some("r1"));
+
+        let profiles = client.list_profiles().unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[1].site, "b.com");
+        assert_eq!(profiles[1].counter, 3);
+
+        let created = client
+            .create_profile(&RemoteProfile::from(&Profile::new("c", "c.com", "me")))
+            .unwrap();
+        assert_eq!(created.id.as_deref(), Some("3"));
+        client.delete_profile("3").unwrap();
+
+        assert!(matches!(
+            client.update_profile(&RemoteProfile::from(&Profile::default())),
+            Err(ClientError::MissingId)
+        ));
+
+        client.set_tokens(Some("expired"), None);
+        assert!(matches!(
+            client.list_profiles(),
+            Err(ClientError::Status(401))
+        ));
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "POST /api/auth/jwt/create/ HTTP/1.1",
+                "GET /api/passwords/ HTTP/1.1",
+                "GET /api/passwords/?page=2 HTTP/1.1",
+                "POST /api/passwords/ HTTP/1.1",
+                "DELETE /api/passwords/3/ HTTP/1.1",
+                "GET /api/passwords/ HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn hostile_pages() {
+        let (base_url, requests) = server();
+
+        // The token must not be sent to another server
+        let mut client = Client::new(&format!("{}/foreign", base_url));
+        client.set_tokens(Some("a1"), None);
+        assert!(matches!(
+            client.list_profiles(),
+            Err(ClientError::InvalidPage)
+        ));
+
+        let mut client = Client::new(&format!("{}/loop", base_url));
+        client.set_tokens(Some("a1"), None);
+        assert!(matches!(
+            client.list_profiles(),
+            Err(ClientError::InvalidPage)
+        ));
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "GET /foreign/api/passwords/ HTTP/1.1",
+                "GET /loop/api/passwords/ HTTP/1.1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+/// Parse RFC 4180 CSV `input` into its records, the first one usually being the header.
+///
+/// Return `None` if a quoted field is not terminated.
+pub fn parse(input: &str) -> Option<Vec<Vec<String>>> {
+    let input = input.trim_start_matches('\u{feff}');
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    // Skip the blank lines
+    records.retain(|record| record.len() > 1 || record.iter().any(|field| !field.is_empty()));
+    Some(records)
+}
+
+/// The records of a CSV file, with its header.
+#[derive(Debug)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Parse `input`, whose first record is the header.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut records = parse(input)?.into_iter();
+        let header = records
+            .next()?
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        Some(Self {
+            header,
+            rows: records.collect(),
+        })
+    }
+
+    /// Index of the first column named like one of the `names`, ignoring the case.
+    pub fn column(&self, names: &[&str]) -> Option<usize> {
+        names
+            .iter()
+            .find_map(|name| self.header.iter().position(|column| column == name))
+    }
+
+    /// The records, without the header.
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+}
+
+/// Value of the `column` of the `row`, empty if missing.
+pub fn get(row: &[String], column: Option<usize>) -> &str {
+    column
+        .and_then(|column| row.get(column))
+        .map_or("", |value| value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_records() {
+        assert_eq!(
+            parse("a,b,c\r\n1,\"two, \"\"2\"\"\",\"multi\nline\"\n\n4,,6").unwrap(),
+            [
+                vec!["a", "b", "c"],
+                vec!["1", "two, \"2\"", "multi\nline"],
+                vec!["4", "", "6"]
+            ]
+        );
+        assert_eq!(parse("\u{feff}a\n").unwrap(), [vec!["a"]]);
+        assert!(parse("a,\"b\n").is_none());
+    }
+
+    #[test]
+    fn table_columns() {
+        let table = Table::parse("Name, URL ,login_uri\nx,y,z").unwrap();
+        assert_eq!(table.column(&["url"]), Some(1));
+        assert_eq!(table.column(&["website", "login_uri"]), Some(2));
+        assert_eq!(table.column(&["password"]), None);
+        assert_eq!(get(&table.rows()[0], table.column(&["name"])), "x");
+        assert_eq!(get(&table.rows()[0], None), "");
+    }
+}
@@ -3,22 +3,25 @@ use num_bigint::BigUint;
 use crate::algo::Algorithm;
 use crate::hex::to_hex;
 use crate::master::Master;
+use crate::LessPassError;
 
+/// The entropy derived by PBKDF2 from the master password and the salt of a site, consumed
+/// to pick the characters of a password.
 #[derive(Debug, Clone)]
 pub struct Entropy(BigUint);
 
 impl Entropy {
     /// Return a salt, combining `site`, `login` and `counter` from strings.
-    pub fn salt(site: &str, login: &str, counter: u32) -> Vec<u8> {
+    pub(crate) fn salt(site: &str, login: &str, counter: u32) -> Vec<u8> {
         Self::salt_byte(site.as_bytes(), login.as_bytes(), &to_hex(counter))
     }
     /// Return a salt, combining `site`, `login` and `counter` from byte array.
-    pub fn salt_byte(site: &[u8], login: &[u8], counter: &[u8]) -> Vec<u8> {
+    pub(crate) fn salt_byte(site: &[u8], login: &[u8], counter: &[u8]) -> Vec<u8> {
         [site, login, counter].concat()
     }
 
     /// Generate the entropy, from the master password, a salt and a number of iterations
-    pub fn new(algorithm: Algorithm, master: &Master, salt: &[u8], iterations: u32) -> Self {
+    pub(crate) fn new(algorithm: Algorithm, master: &Master, salt: &[u8], iterations: u32) -> Self {
         Self(BigUint::from_bytes_be(&algorithm.pbkdf2(
             master.bytes(),
             salt,
@@ -31,17 +34,30 @@ impl Entropy {
     /// It gives us quotient and a remainder.
     /// Remainder is always between 0 and length of pool of chars.
     /// We use it as an index in pool of chars for the first letter of our generated password.
-    pub fn consume(&mut self, len: &BigUint) -> usize {
+    ///
+    /// Once exhausted, the entropy is 0 and every index is 0, like the canonical
+    /// implementation: see [`Entropy::remaining_bits`] to detect it.
+    ///
+    /// # Errors
+    ///
+    /// A [`LessPassError::NoCharsetSelected`] if the pool of chars is empty, `len` being 0.
+    pub fn consume(&mut self, len: usize) -> Result<usize, LessPassError> {
         use num_integer::Integer;
-        use num_traits::ToPrimitive;
 
-        let (quot, rem) = self.0.div_rem(len);
+        if len == 0 {
+            return Err(LessPassError::NoCharsetSelected);
+        }
+        let (quot, rem) = self.0.div_rem(&BigUint::from(len));
         self.0 = quot;
 
-        match rem.to_u64() {
-            Some(rem) => rem as usize,
-            None => unreachable!(),
-        }
+        // The remainder is less than `len`, its only digit fits in a `usize` too
+        Ok(rem.iter_u64_digits().next().unwrap_or(0) as usize)
+    }
+
+    /// Number of bits of entropy not consumed yet, 0 once it is exhausted.
+    #[must_use]
+    pub fn remaining_bits(&self) -> u64 {
+        self.0.bits()
     }
 }
 
@@ -78,4 +94,23 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn consume_until_exhausted() {
+        let master = Master::new("password", Algorithm::SHA256).unwrap();
+        let mut e = Entropy::new(Algorithm::SHA256, &master, b"salt", 1);
+        let bits = e.remaining_bits();
+        assert!(bits > 248 && bits <= 256);
+
+        // 16 is 4 bits
+        e.consume(16).unwrap();
+        assert_eq!(e.remaining_bits(), bits - 4);
+
+        assert_eq!(e.consume(0), Err(LessPassError::NoCharsetSelected));
+
+        while e.remaining_bits() > 0 {
+            e.consume(2).unwrap();
+        }
+        assert_eq!(e.consume(94), Ok(0));
+    }
 }
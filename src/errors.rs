@@ -27,6 +27,27 @@ pub enum LessPassError {
 
     /// The provided string is not a valid base32 encoded string
     InvalidBase32,
+
+    /// The external HMAC provider failed to compute the HMAC.
+    HmacProviderFailure,
+
+    /// The provided string is not a valid `otpauth://` URI.
+    InvalidUri,
+
+    /// The OTP self-test failed: the algorithm does not produce the expected tokens.
+    SelfTestFailed(Algorithm),
+
+    /// The provided backup, or export of another application, cannot be read.
+    InvalidBackup,
+
+    /// The provided backup cannot be decrypted with the provided password.
+    DecryptionFailed,
+
+    /// The backup cannot be encrypted.
+    EncryptionFailed,
+
+    /// The charset cannot be used to generate a password.
+    InvalidCharset,
 }
 
 impl fmt::Display for LessPassError {
@@ -44,6 +65,20 @@ impl fmt::Display for LessPassError {
                 f.write_str("The number of digits is not valid."),
             Self::InvalidBase32 =>
                 f.write_str("The provided string is not a valid base32 encoded string."),
+            Self::HmacProviderFailure =>
+                f.write_str("The HMAC provider failed to compute the HMAC."),
+            Self::InvalidUri =>
+                f.write_str("The provided string is not a valid otpauth URI."),
+            Self::SelfTestFailed(algorithm) =>
+                f.write_str(format!("The OTP self-test failed for algorithm {}.", algorithm).as_str()),
+            Self::InvalidBackup =>
+                f.write_str("The provided backup is not valid."),
+            Self::DecryptionFailed =>
+                f.write_str("The provided backup cannot be decrypted with this password."),
+            Self::EncryptionFailed =>
+                f.write_str("The backup cannot be encrypted."),
+            Self::InvalidCharset =>
+                f.write_str("The charset cannot be used to generate a password."),
         }
     }
 }
@@ -78,5 +113,33 @@ mod tests {
             LessPassError::InvalidBase32.to_string(),
             "The provided string is not a valid base32 encoded string."
         );
+        assert_eq!(
+            LessPassError::HmacProviderFailure.to_string(),
+            "The HMAC provider failed to compute the HMAC."
+        );
+        assert_eq!(
+            LessPassError::InvalidUri.to_string(),
+            "The provided string is not a valid otpauth URI."
+        );
+        assert_eq!(
+            LessPassError::SelfTestFailed(Algorithm::SHA512).to_string(),
+            "The OTP self-test failed for algorithm Sha2-512."
+        );
+        assert_eq!(
+            LessPassError::InvalidBackup.to_string(),
+            "The provided backup is not valid."
+        );
+        assert_eq!(
+            LessPassError::DecryptionFailed.to_string(),
+            "The provided backup cannot be decrypted with this password."
+        );
+        assert_eq!(
+            LessPassError::EncryptionFailed.to_string(),
+            "The backup cannot be encrypted."
+        );
+        assert_eq!(
+            LessPassError::InvalidCharset.to_string(),
+            "The charset cannot be used to generate a password."
+        );
     }
 }
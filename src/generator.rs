@@ -0,0 +1,154 @@
+use crate::{Algorithm, Entropy, LessPassError, Settings};
+
+/// Build a password from the [`Entropy`] of a site.
+///
+/// [`Settings`] is the generator of LessPass, picking characters in a charset; others, like
+/// passphrases or patterns, could be plugged with [`LessPass::generate`](crate::LessPass::generate)
+/// while reusing the salt and PBKDF2 handling.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{Algorithm, Entropy, Generator, LessPass, LessPassError};
+///
+/// /// A PIN of 4 digits.
+/// struct Pin;
+///
+/// impl Generator for Pin {
+///     fn generate(&self, entropy: &mut Entropy) -> Result<String, LessPassError> {
+///         (0..4)
+///             .map(|_| entropy.consume(10).map(|digit| digit.to_string()))
+///             .collect()
+///     }
+/// }
+///
+/// let lp = LessPass::new("My5ecr3!", Algorithm::SHA256)?;
+/// let pin = lp.generate("example.com", "test@example.com", 1, &Pin)?;
+/// assert_eq!(pin.len(), 4);
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+pub trait Generator {
+    /// Generate the password, consuming the `entropy`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`Entropy::consume`], or the ones of the generator.
+    fn generate(&self, entropy: &mut Entropy) -> Result<String, LessPassError>;
+
+    /// Algorithm of PBKDF2 deriving the entropy, `None` for the one of the master password.
+    fn algorithm(&self) -> Option<Algorithm> {
+        None
+    }
+
+    /// Number of iterations of PBKDF2 deriving the entropy, 100,000 like LessPass.
+    fn iterations(&self) -> u32 {
+        100_000
+    }
+}
+
+impl Generator for Settings {
+    /// Generate the password like LessPass.
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::NoCharsetSelected`] if no characters are asked in the password.
+    /// * [`LessPassError::PasswordTooShort`] if the password cannot have a character of
+    ///   each charset, and another one.
+    /// * [`LessPassError::InvalidCharset`] if the picked characters do not make a valid string.
+    fn generate(&self, entropy: &mut Entropy) -> Result<String, LessPassError> {
+        let charset = self.get_characterset();
+        let chars = charset.get_chars().as_bytes();
+        if chars.is_empty() {
+            return Err(LessPassError::NoCharsetSelected);
+        }
+        let count = charset.get_charset_count();
+        let password_len = self.get_password_len();
+        if password_len as usize <= count {
+            return Err(LessPassError::PasswordTooShort(
+                count as u8 + 1,
+                password_len,
+            ));
+        }
+        let max_len = password_len as usize - count;
+        let mut password = Vec::with_capacity(password_len as usize);
+
+        // Step 1:
+        // get random char from charset, of password_len - number_of_charset length to generate a
+        // temporary password
+        for _ in 0..max_len {
+            let rem = entropy.consume(chars.len())?;
+            password.push(chars[rem]);
+        }
+
+        // Step 2:
+        // get one character per charset to add later to the password to add later to the
+        // temporary password
+        let mut additional_pass = Vec::with_capacity(charset.get_serials().len());
+        for serial in charset.get_serials() {
+            let serial = charset.get_serial(*serial).as_bytes();
+            let rem = entropy.consume(serial.len())?;
+            additional_pass.push(serial[rem])
+        }
+
+        // Step 3:
+        // add additional characters to the password to generate final password
+        for char in additional_pass {
+            let rem = entropy.consume(password.len())?;
+            password.insert(rem, char);
+        }
+
+        String::from_utf8(password).map_err(|_| LessPassError::InvalidCharset)
+    }
+
+    fn algorithm(&self) -> Option<Algorithm> {
+        self.get_algorithm()
+    }
+
+    fn iterations(&self) -> u32 {
+        self.get_iterations()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::charset::{LowerCase, Numbers, Symbols, UpperCase};
+    use crate::{Algorithm, LessPass, LessPassError, Settings};
+
+    #[test]
+    fn settings_generator() {
+        let lp = LessPass::new("My5ecr3!", Algorithm::SHA256).unwrap();
+        let mut settings = Settings::default();
+        settings.set_iterations(1000);
+        settings.set_algorithm(Algorithm::SHA512);
+        assert_eq!(
+            lp.generate("example.com", "test@example.com", 1, &settings),
+            lp.password("example.com", "test@example.com", 1, &settings)
+        );
+
+        // Not validated by LessPass::password, but never panicking
+        let short = Settings::new(
+            4,
+            LowerCase::Using,
+            UpperCase::Using,
+            Numbers::Using,
+            Symbols::Using,
+        );
+        assert_eq!(
+            lp.generate("example.com", "test@example.com", 1, &short),
+            Err(LessPassError::PasswordTooShort(5, 4))
+        );
+
+        let none = Settings::new(
+            16,
+            LowerCase::NotUsing,
+            UpperCase::NotUsing,
+            Numbers::NotUsing,
+            Symbols::NotUsing,
+        );
+        assert_eq!(
+            lp.generate("example.com", "test@example.com", 1, &none),
+            Err(LessPassError::NoCharsetSelected)
+        );
+    }
+}
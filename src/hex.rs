@@ -2,9 +2,7 @@ const HEX: &[u8] = b"0123456789abcdef";
 
 pub fn to_hex(num: u32) -> Vec<u8> {
     if num < 16 {
-        let mut h = Vec::with_capacity(1);
-        h.push(HEX[num as usize]);
-        h
+        vec![HEX[num as usize]]
     } else {
         let mut h = hex(num);
         while h[0] == HEX[0] {
@@ -0,0 +1,711 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+#[cfg(feature = "aegis")]
+use crate::aegis::{Entry, Vault};
+use crate::csv::{self, Table};
+use crate::keyring::{OtpProfile, Profile};
+use crate::{decode_base32, Algorithm, LessPass, LessPassError, Otp, OtpProvisioning};
+
+/// An entry of an export which cannot be imported, like an OTP whose secret is not valid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    /// Position of the entry in the export, from 0: the row of a CSV without its header,
+    /// the OTP of a vault, or the non-blank line of a list of URIs.
+    pub index: usize,
+    /// Name of the entry, empty if it cannot be read.
+    pub name: String,
+    /// Why the entry cannot be imported.
+    pub error: LessPassError,
+}
+
+/// Result of an import: the entries which cannot be imported are skipped, to be reported to
+/// the user, instead of failing the whole import.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Import {
+    /// The imported profiles.
+    pub profiles: Vec<Profile>,
+    /// The entries which cannot be imported.
+    pub skipped: Vec<Skipped>,
+}
+
+impl Import {
+    /// Add the profile of the entry at `index`, or skip it.
+    fn add(&mut self, index: usize, name: String, profile: Result<Profile, LessPassError>) {
+        match profile {
+            Ok(profile) => self.profiles.push(profile),
+            Err(error) => self.skipped.push(Skipped { index, name, error }),
+        }
+    }
+}
+
+/// Import the logins of a Bitwarden CSV export.
+///
+/// The other items, like the secure notes or the cards, are ignored. The TOTP, an
+/// `otpauth://` URI or a base32 secret, is encrypted with the master password.
+///
+/// Every profile has the default settings: if the stored password is not the one derived
+/// by LessPass, the profile [`needs_rotation`](Profile::needs_rotation). The logins whose
+/// TOTP is not valid, or cannot be encrypted, are [skipped](Import::skipped).
+///
+/// ## Notes
+///
+/// To compare the passwords, each one is derived with the 100,000 iterations of PBKDF2: the
+/// import of a large export takes a while, it should not run in a UI thread.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{import, Algorithm, LessPass};
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let csv = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+///            ,,login,GitHub,,,,https://github.com/login,me,hunter2,";
+///
+/// let profiles = import::bitwarden_csv(csv, &lp)?.profiles;
+/// assert_eq!(profiles[0].name, "GitHub");
+/// assert_eq!(profiles[0].site, "github.com");
+/// assert!(profiles[0].needs_rotation);
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+///
+/// # Errors
+///
+/// A [`LessPassError::InvalidBackup`] if `csv` is not a Bitwarden export.
+pub fn bitwarden_csv(csv: &str, lesspass: &LessPass) -> Result<Import, LessPassError> {
+    let table = Table::parse(csv).ok_or(LessPassError::InvalidBackup)?;
+    let columns = Columns {
+        name: table.column(&["name"]),
+        url: table.column(&["login_uri"]),
+        login: table.column(&["login_username"]),
+        password: table.column(&["login_password"]),
+        notes: table.column(&["notes"]),
+        totp: table.column(&["login_totp"]),
+    };
+    if columns.url.is_none() || columns.login.is_none() {
+        return Err(LessPassError::InvalidBackup);
+    }
+    let kind = table.column(&["type"]);
+
+    let mut import = Import::default();
+    for (index, row) in table.rows().iter().enumerate() {
+        if matches!(csv::get(row, kind), "" | "login") {
+            import.add(index, columns.name(row), columns.profile(row, lesspass));
+        }
+    }
+    Ok(import)
+}
+
+/// Import the logins of a 1Password CSV export.
+///
+/// Both the columns of 1Password 8 (`Title`, `Url`, `Username`, `Password`, `OTPAuth`,
+/// `Notes`) and the ones of the older versions (`website`, `notesPlain`) are read. As
+/// with [`bitwarden_csv`], the profiles whose password is not the derived one
+/// [need a rotation](Profile::needs_rotation), and the logins whose TOTP is not valid are
+/// skipped.
+///
+/// # Errors
+///
+/// See [`bitwarden_csv`].
+pub fn onepassword_csv(csv: &str, lesspass: &LessPass) -> Result<Import, LessPassError> {
+    let table = Table::parse(csv).ok_or(LessPassError::InvalidBackup)?;
+    let columns = Columns {
+        name: table.column(&["title"]),
+        url: table.column(&["url", "website", "urls"]),
+        login: table.column(&["username"]),
+        password: table.column(&["password"]),
+        notes: table.column(&["notes", "notesplain"]),
+        totp: table.column(&["otpauth", "one-time password"]),
+    };
+    if columns.url.is_none() || columns.login.is_none() {
+        return Err(LessPassError::InvalidBackup);
+    }
+
+    let mut import = Import::default();
+    for (index, row) in table.rows().iter().enumerate() {
+        import.add(index, columns.name(row), columns.profile(row, lesspass));
+    }
+    Ok(import)
+}
+
+/// Import the passwords exported by a web browser: Chrome, Firefox, Edge or Safari.
+///
+/// The profiles are named after their site, and have the default settings; the duplicates
+/// of a site and a login, usually saved for several pages, are ignored. As with
+/// [`bitwarden_csv`], the profiles whose password is not the derived one
+/// [need a rotation](Profile::needs_rotation), and the logins whose TOTP is not valid are
+/// skipped.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{import, Algorithm, LessPass};
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let csv = "name,url,username,password,note\n\
+///            github.com,https://github.com/login,me,hunter2,";
+///
+/// let profiles = import::browser_csv(csv, &lp)?.profiles;
+/// assert_eq!(profiles[0].site, "github.com");
+/// assert!(profiles[0].needs_rotation);
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+///
+/// # Errors
+///
+/// See [`bitwarden_csv`].
+pub fn browser_csv(csv: &str, lesspass: &LessPass) -> Result<Import, LessPassError> {
+    let table = Table::parse(csv).ok_or(LessPassError::InvalidBackup)?;
+    let columns = Columns {
+        // Only Safari exports a title, the names of Chrome are the sites
+        name: table.column(&["title"]),
+        url: table.column(&["url"]),
+        login: table.column(&["username"]),
+        password: table.column(&["password"]),
+        notes: table.column(&["note", "notes"]),
+        totp: table.column(&["otpauth"]),
+    };
+    if columns.url.is_none() || columns.login.is_none() {
+        return Err(LessPassError::InvalidBackup);
+    }
+
+    let mut import = Import::default();
+    for (index, row) in table.rows().iter().enumerate() {
+        let login = csv::get(row, columns.login);
+        let site = site(csv::get(row, columns.url));
+        if import
+            .profiles
+            .iter()
+            .any(|profile| profile.site == site && profile.login == login)
+        {
+            continue;
+        }
+        import.add(index, columns.name(row), columns.profile(row, lesspass));
+    }
+    Ok(import)
+}
+
+/// Import the TOTP and HOTP of an Aegis Authenticator vault, encrypted with `password` or
+/// exported in clear.
+///
+/// A profile is created for each OTP, named after its issuer, with the default settings;
+/// the proprietary OTP, like the Steam ones, are ignored. The OTP secrets are encrypted
+/// with the master password, the OTP which are not valid or cannot be encrypted are
+/// [skipped](Import::skipped). The passwords of the sites being unknown, every profile
+/// [needs a rotation](Profile::needs_rotation).
+///
+/// # Errors
+///
+/// * [`LessPassError::InvalidBackup`] if `json` is not an Aegis vault.
+/// * [`LessPassError::DecryptionFailed`] if `password` is not the one of the vault.
+#[cfg(feature = "aegis")]
+pub fn aegis_json(
+    json: &str,
+    password: &str,
+    lesspass: &LessPass,
+) -> Result<Import, LessPassError> {
+    let db = Vault::open(json, password)?;
+
+    let mut import = Import::default();
+    for (index, entry) in db.entries.iter().enumerate() {
+        let hotp = match entry.kind.as_str() {
+            "totp" => false,
+            "hotp" => true,
+            _ => continue,
+        };
+        let name = if entry.issuer.is_empty() {
+            &entry.name
+        } else {
+            &entry.issuer
+        };
+        import.add(index, name.clone(), aegis_profile(entry, hotp, lesspass));
+    }
+    Ok(import)
+}
+
+/// Create the profile of a TOTP, or HOTP if `hotp`, of an Aegis vault.
+#[cfg(feature = "aegis")]
+fn aegis_profile(entry: &Entry, hotp: bool, lesspass: &LessPass) -> Result<Profile, LessPassError> {
+    let algorithm = match entry.info.algo.to_ascii_uppercase().as_str() {
+        "SHA1" => Algorithm::SHA1,
+        "SHA256" => Algorithm::SHA256,
+        "SHA512" => Algorithm::SHA512,
+        _ => return Err(LessPassError::UnsupportedAlgorithm),
+    };
+    let otp = Otp::new(
+        &decode_base32(&entry.info.secret.to_ascii_uppercase())?,
+        entry.info.digits,
+        Some(algorithm),
+        entry.info.period,
+        None,
+    )?;
+
+    let mut provisioning = OtpProvisioning::new(&entry.name);
+    if !entry.issuer.is_empty() {
+        provisioning.set_issuer(Some(&entry.issuer));
+    }
+    if hotp {
+        provisioning.set_counter(Some(entry.info.counter.unwrap_or_default()));
+    }
+
+    let mut profile = otp_profile(&otp, provisioning, lesspass)?;
+    if !entry.note.is_empty() {
+        profile.notes = Some(entry.note.clone());
+    }
+    Ok(profile)
+}
+
+/// Import the tokens of a FreeOTP+ JSON export.
+///
+/// A profile is created for each token, named after its issuer, with the default settings;
+/// the tokens which are not valid or cannot be encrypted are [skipped](Import::skipped).
+/// The passwords of the sites being unknown, every profile
+/// [needs a rotation](Profile::needs_rotation).
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{import, Algorithm, LessPass};
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let json = r#"{"tokenOrder": ["GitHub:me"], "tokens": [{"algo": "SHA1", "counter": 0,
+///     "digits": 6, "issuerExt": "GitHub", "label": "me", "period": 30,
+///     "secret": [72, 101, 108, 108, 111, 33, -34, -83, -66, -17], "type": "TOTP"}]}"#;
+///
+/// let profiles = import::freeotp_json(json, &lp)?.profiles;
+/// assert_eq!(profiles[0].name, "GitHub");
+/// assert_eq!(profiles[0].login, "me");
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+///
+/// # Errors
+///
+/// A [`LessPassError::InvalidBackup`] if `json` is not a FreeOTP+ export.
+#[cfg(feature = "serde")]
+pub fn freeotp_json(json: &str, lesspass: &LessPass) -> Result<Import, LessPassError> {
+    let export: FreeOtpExport =
+        serde_json::from_str(json).map_err(|_| LessPassError::InvalidBackup)?;
+
+    let mut import = Import::default();
+    for (index, token) in export.tokens.iter().enumerate() {
+        let name = token.issuer().unwrap_or(&token.label).to_owned();
+        import.add(index, name, token.profile(lesspass));
+    }
+    Ok(import)
+}
+
+/// Import a list of `otpauth://` URIs, one per line, like the URI export of FreeOTP+.
+///
+/// The blank lines are ignored. A profile is created for each OTP, named after its issuer,
+/// and [needs a rotation](Profile::needs_rotation); the URIs which are not valid, or whose
+/// secret cannot be encrypted, are [skipped](Import::skipped).
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{import, Algorithm, LessPass};
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let uris = "otpauth://totp/GitHub:me?secret=JBSWY3DPEHPK3PXP&issuer=GitHub\n\
+///             otpauth://hotp/bob?secret=JBSWY3DPEHPK3PXP&counter=5\n";
+///
+/// let import = import::otpauth_uris(uris, &lp);
+/// assert_eq!(import.profiles[0].name, "GitHub");
+/// assert_eq!(import.profiles[1].name, "bob");
+/// assert!(import.skipped.is_empty());
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+#[must_use]
+pub fn otpauth_uris(uris: &str, lesspass: &LessPass) -> Import {
+    let mut import = Import::default();
+    let lines = uris.lines().map(str::trim).filter(|line| !line.is_empty());
+    for (index, uri) in lines.enumerate() {
+        // The URI holds the secret, it is not reported
+        match OtpProvisioning::from_uri(uri) {
+            Ok((otp, provisioning)) => {
+                let name = provisioning
+                    .get_issuer()
+                    .unwrap_or_else(|| provisioning.get_label())
+                    .to_owned();
+                import.add(index, name, otp_profile(&otp, provisioning, lesspass));
+            }
+            Err(error) => import.add(index, String::new(), Err(error)),
+        }
+    }
+    import
+}
+
+/// A FreeOTP+ JSON export.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct FreeOtpExport {
+    tokens: Vec<FreeOtpToken>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FreeOtpToken {
+    #[serde(default = "default_algo")]
+    algo: String,
+    #[serde(default)]
+    counter: u64,
+    #[serde(default = "default_digits")]
+    digits: u8,
+    #[serde(default)]
+    issuer_ext: Option<String>,
+    #[serde(default)]
+    issuer_int: Option<String>,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    period: Option<u32>,
+    secret: Vec<i8>,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[cfg(feature = "serde")]
+fn default_algo() -> String {
+    "SHA1".to_owned()
+}
+
+#[cfg(feature = "serde")]
+const fn default_digits() -> u8 {
+    6
+}
+
+#[cfg(feature = "serde")]
+impl FreeOtpToken {
+    fn issuer(&self) -> Option<&str> {
+        let issuer = self.issuer_ext.as_deref().or(self.issuer_int.as_deref());
+        issuer.filter(|issuer| !issuer.is_empty())
+    }
+
+    /// Create the profile of the token.
+    fn profile(&self, lesspass: &LessPass) -> Result<Profile, LessPassError> {
+        let algorithm = match self.algo.to_ascii_uppercase().as_str() {
+            "SHA1" => Algorithm::SHA1,
+            "SHA256" => Algorithm::SHA256,
+            "SHA512" => Algorithm::SHA512,
+            _ => return Err(LessPassError::UnsupportedAlgorithm),
+        };
+        // The secret is exported as Java bytes, which are signed
+        let secret = self
+            .secret
+            .iter()
+            .map(|&byte| byte as u8)
+            .collect::<Vec<_>>();
+        let otp = Otp::new(&secret, self.digits, Some(algorithm), self.period, None)?;
+
+        let mut provisioning = OtpProvisioning::new(&self.label);
+        if let Some(issuer) = self.issuer() {
+            provisioning.set_issuer(Some(issuer));
+        }
+        if self.kind.eq_ignore_ascii_case("hotp") {
+            provisioning.set_counter(Some(self.counter));
+        }
+        otp_profile(&otp, provisioning, lesspass)
+    }
+}
+
+/// Create the profile of an OTP imported from an authenticator, named after its issuer.
+///
+/// The password of the site is unknown, so the profile needs a rotation.
+fn otp_profile(
+    otp: &Otp,
+    provisioning: OtpProvisioning,
+    lesspass: &LessPass,
+) -> Result<Profile, LessPassError> {
+    let login = provisioning.get_label().to_owned();
+    let name = provisioning.get_issuer().unwrap_or(&login).to_owned();
+
+    let mut profile = Profile::new(&name, &name, &login);
+    profile.needs_rotation = true;
+    profile.otp = Some(OtpProfile::encrypt(
+        lesspass,
+        &profile.site,
+        &profile.login,
+        otp,
+        provisioning,
+    )?);
+    Ok(profile)
+}
+
+/// Columns of a password manager export.
+struct Columns {
+    name: Option<usize>,
+    url: Option<usize>,
+    login: Option<usize>,
+    password: Option<usize>,
+    notes: Option<usize>,
+    totp: Option<usize>,
+}
+
+impl Columns {
+    /// Name of the profile of a `row`, its site if it has no name.
+    fn name(&self, row: &[String]) -> String {
+        match csv::get(row, self.name) {
+            "" => site(csv::get(row, self.url)),
+            name => name.to_owned(),
+        }
+    }
+
+    /// Create the profile of a `row`, with the default settings.
+    fn profile(&self, row: &[String], lesspass: &LessPass) -> Result<Profile, LessPassError> {
+        let site = site(csv::get(row, self.url));
+        let login = csv::get(row, self.login);
+        let name = self.name(row);
+
+        let mut profile = Profile::new(&name, if site.is_empty() { &name } else { &site }, login);
+        let notes = csv::get(row, self.notes);
+        if !notes.is_empty() {
+            profile.notes = Some(notes.to_owned());
+        }
+        profile.needs_rotation = needs_rotation(&profile, csv::get(row, self.password), lesspass)?;
+
+        let totp = csv::get(row, self.totp);
+        if !totp.is_empty() {
+            let (otp, provisioning) = otp(totp, login)?;
+            profile.otp = Some(OtpProfile::encrypt(
+                lesspass,
+                &profile.site,
+                &profile.login,
+                &otp,
+                provisioning,
+            )?);
+        }
+        Ok(profile)
+    }
+}
+
+/// Whether the stored `password` differs from the one derived for the `profile`.
+fn needs_rotation(
+    profile: &Profile,
+    password: &str,
+    lesspass: &LessPass,
+) -> Result<bool, LessPassError> {
+    Ok(password.is_empty() || profile.password(lesspass)? != password)
+}
+
+/// Host name of an `url`, as used by LessPass: without scheme, port, path nor `www.`.
+fn site(url: &str) -> String {
+    let url = url.trim();
+    let host = url.find("://").map_or(url, |index| &url[index + 3..]);
+    let host = host.split(&['/', '?', '#'][..]).next().unwrap_or_default();
+    let host = host.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default().to_lowercase();
+    host.trim_start_matches("www.").to_owned()
+}
+
+/// Parse a TOTP field: an `otpauth://` URI, or a base32 secret of the default TOTP.
+fn otp(field: &str, login: &str) -> Result<(Otp, OtpProvisioning), LessPassError> {
+    if field.to_lowercase().starts_with("otpauth://") {
+        return OtpProvisioning::from_uri(field);
+    }
+
+    let secret = field.replace(' ', "").to_uppercase();
+    let otp = Otp::new(
+        &decode_base32(&secret)?,
+        6,
+        Some(Algorithm::SHA1),
+        Some(30),
+        None,
+    )?;
+    Ok((otp, OtpProvisioning::new(login)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn site_of_url() {
+        assert_eq!(site("https://www.GitHub.com/login?next=/"), "github.com");
+        assert_eq!(site("http://me@example.com:8080#top"), "example.com");
+        assert_eq!(site("example.org/path"), "example.org");
+        assert_eq!(site(""), "");
+    }
+
+    #[test]
+    fn import_bitwarden() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let csv = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+            ,,note,Secret note,text,,,,,,\n\
+            ,1,login,LessPass,\"two\nlines\",,0,https://lesspass.com,contact@lesspass.com,OlfK63bmUhqrGODR,\n\
+            ,,login,Example,,,0,https://example.com,me,random,JBSW Y3DP EHPK 3PXP\n\
+            ,,login,Broken,,,0,https://broken.com,me,random,not base32!\n";
+
+        let import = bitwarden_csv(csv, &lesspass).unwrap();
+        let profiles = import.profiles;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(
+            import.skipped,
+            vec![Skipped {
+                index: 3,
+                name: "Broken".to_owned(),
+                error: LessPassError::InvalidBase32,
+            }]
+        );
+
+        let mut expected = Profile::new("LessPass", "lesspass.com", "contact@lesspass.com");
+        expected.notes = Some("two\nlines".to_owned());
+        // Derived with the default settings, symbols included
+        expected.needs_rotation = true;
+        assert_eq!(profiles[0], expected);
+
+        let otp = profiles[1].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_label(), "me");
+        let otp = otp.decrypt(&lesspass, "example.com", "me").unwrap();
+        assert_eq!(otp.get_secret(), Some(&b"Hello!\xde\xad\xbe\xef"[..]));
+
+        assert_eq!(
+            bitwarden_csv("name,url\nx,y", &lesspass),
+            Err(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn import_browser() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let firefox = "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\",\"timeCreated\",\"timeLastUsed\",\"timePasswordChanged\"\n\
+            \"https://example.com\",\"me\",\"pass\",,\"https://example.com\",\"{1}\",\"1\",\"1\",\"1\"\n\
+            \"https://www.example.com/login\",\"me\",\"pass\",,\"https://example.com\",\"{2}\",\"1\",\"1\",\"1\"\n\
+            \"https://example.com\",\"other\",\"pass\",,\"https://example.com\",\"{3}\",\"1\",\"1\",\"1\"\n";
+
+        let profiles = browser_csv(firefox, &lesspass).unwrap().profiles;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "example.com");
+        assert_eq!(profiles[1].login, "other");
+        assert!(profiles.iter().all(|profile| profile.needs_rotation));
+        assert_eq!(profiles[0].settings().get_password_len(), 16);
+
+        assert_eq!(
+            browser_csv("name,username\nx,y", &lesspass),
+            Err(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn import_onepassword() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let derived = Profile::new("", "example.com", "me")
+            .password(&lesspass)
+            .unwrap();
+        let csv = format!(
+            "Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+             Example,https://example.com/,me,{},otpauth://totp/Example:me?secret=JBSWY3DPEHPK3PXP&issuer=Example,false,false,,\n",
+            derived
+        );
+
+        let profiles = onepassword_csv(&csv, &lesspass).unwrap().profiles;
+        assert_eq!(profiles[0].name, "Example");
+        assert!(!profiles[0].needs_rotation);
+        let otp = profiles[0].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_issuer(), Some("Example"));
+    }
+
+    #[cfg(feature = "aegis")]
+    #[test]
+    fn import_aegis() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        // Encrypted with the password "test", holding a TOTP, a HOTP and a Steam OTP
+        let vault = r#"{"version": 1, "header": {"slots": [{"type": 1, "uuid": "a8325752-c1be-458a-9b3e-5e0a8154d9ec", "key": "ab55db31227f3fab60831bcf2ae7ef8a427e04245da6dd9e6e69821d71e121ff", "key_params": {"nonce": "010101010101010101010101", "tag": "cedd26545d5dd85ac004f24fba9a6e61"}, "n": 1024, "r": 8, "p": 1, "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f", "repaired": true}], "params": {"nonce": "020202020202020202020202", "tag": "89a1a28df4cb11b1da2968a7bd9a8009"}}, "db": "icdwZ66PPuzDiKkR+MqT3apwZbfizB7zPbNCPZ/qEVW0JLAtMt23JaONgp/71l5xPF+0ZvgJKR9OMTlLbH+tDdb+0jQEampDm69RUkaEfHLxbl/mKwcXsOn4pIxZt1BTYbNmLQAZ7kSdUji5vDujWe4Kpof/KHox3YBPH3eROaylWi/TPExcalCGEeyncC45mrDKXUUryHsmaemjjP00PD1iD02qCu6uyumWMCYPwt3UC3vfKYrGoTdxulXLdu2wgh+86OKI3PlkMVAg51S/lpGMxJ6NJc5QNEUIBeroRA2ZQ+Surum66I3Xnx0b68Ex/mBVd1hc6CZJtXfADJQy3lIuidPEF249bcMNPNeMKQex+St/VxHOltx3ZsuyLX+P7rmAELcBWGldR4d8YBJNGfsPGfPGWhZibIl0//EVjx1YAMfKkt8Wc6PKg9cbguI9aKZogQWhXZCuR25PMjf6FwgHEKe1ssgYIi4S/gzduVHXzNaWjKdi2fatAwNNRe+MnuT0y4fOi0yQDVCdxy7UZfZxPTCrdZdFoj7dSaLRtYAxM9wDnaQ4KNOtoY8JKYrzZVhHXD2yMg4VLR6M3+AqLTG9jtcAyyr026y02dz0otHqYMXvEhA7ZLSqvVvlGrYJGyuuPKpu2KAM+KEidDNw5GMiRqpo1aiSCItxp5fxYGdQ9PJjDdXAxPEnRfvbgc7VcZ5VaxshveaEFg0QVxsiUSyVylyq7tmEJI4jr5K5nIEAi4NaIhV2UTl2o1tDM0xrRvnRs4sxQ3aIYuFw6JvlRuj2acaAeIBaoCwbPJ4wT8O8WtMEH28HsYDV7ASfxNPObfAj1nVy5AsT/HRT4u5d+yFAYF4qTnWQyr7tBgr4UtbtmvaEifkPRnNNdjt/yhCne5Yivw6FCkmzgFbrLJCCwD+SP62Z1DizckKp3WuP"}"#;
+
+        let profiles = aegis_json(vault, "test", &lesspass).unwrap().profiles;
+        assert_eq!(profiles.len(), 2);
+
+        assert_eq!(profiles[0].name, "GitHub");
+        assert_eq!(profiles[0].login, "me@example.com");
+        assert_eq!(profiles[0].notes.as_deref(), Some("recovery in safe"));
+        assert!(profiles[0].needs_rotation);
+        let otp = profiles[0].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_issuer(), Some("GitHub"));
+        let otp = otp.decrypt(&lesspass, "GitHub", "me@example.com").unwrap();
+        assert_eq!(otp.get_secret(), Some(&b"Hello!\xde\xad\xbe\xef"[..]));
+
+        let otp = profiles[1].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_counter(), Some(5));
+        assert_eq!(otp.algorithm, Algorithm::SHA256);
+        assert_eq!(otp.digits, 8);
+        let otp = otp.decrypt(&lesspass, "ACME", "bob").unwrap();
+        assert_eq!(otp.get_secret(), Some(&b"12345678901234567890"[..]));
+
+        assert_eq!(
+            aegis_json(vault, "wrong", &lesspass),
+            Err(LessPassError::DecryptionFailed)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn import_freeotp() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let json = r#"{"tokenOrder": ["ACME:bob", "me"], "tokens": [
+            {"algo": "SHA256", "counter": 5, "digits": 8, "issuerExt": "ACME", "issuerInt": "ACME",
+             "label": "bob", "period": 30, "secret": [49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+             49, 50, 51, 52, 53, 54, 55, 56, 57, 48], "type": "HOTP"},
+            {"algo": "SHA1", "digits": 6, "label": "me", "period": 60,
+             "secret": [72, 101, 108, 108, 111, 33, -34, -83, -66, -17], "type": "TOTP"}]}"#;
+
+        let profiles = freeotp_json(json, &lesspass).unwrap().profiles;
+        assert_eq!(profiles.len(), 2);
+
+        assert_eq!(profiles[0].name, "ACME");
+        let otp = profiles[0].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_counter(), Some(5));
+        assert_eq!(otp.algorithm, Algorithm::SHA256);
+        assert_eq!(otp.digits, 8);
+
+        assert_eq!(profiles[1].name, "me");
+        assert!(profiles[1].needs_rotation);
+        let otp = profiles[1].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_counter(), None);
+        let otp = otp.decrypt(&lesspass, "me", "me").unwrap();
+        assert_eq!(otp.get_period(), 60);
+        assert_eq!(otp.get_secret(), Some(&b"Hello!\xde\xad\xbe\xef"[..]));
+
+        assert_eq!(
+            freeotp_json("{\"tokens\": [{}]}", &lesspass),
+            Err(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn import_otpauth_uris() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        // The secret of 32 bytes cannot be encrypted
+        let uris = "otpauth://totp/ACME%20Co:john?secret=JBSWY3DPEHPK3PXP\r\n\r\n\
+                    otpauth://totp/Big:me?algorithm=SHA256&secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA\n\
+                    not an uri\n\
+                    otpauth://hotp/bob?secret=JBSWY3DPEHPK3PXP&counter=3\n";
+
+        let import = otpauth_uris(uris, &lesspass);
+        let profiles = import.profiles;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].site, "ACME Co");
+        assert_eq!(profiles[0].login, "john");
+        let otp = profiles[1].otp.as_ref().unwrap();
+        assert_eq!(otp.provisioning.get_counter(), Some(3));
+
+        assert_eq!(
+            import.skipped,
+            vec![
+                Skipped {
+                    index: 1,
+                    name: "Big".to_owned(),
+                    error: LessPassError::InvalidLength,
+                },
+                Skipped {
+                    index: 2,
+                    name: String::new(),
+                    error: LessPassError::InvalidUri,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,633 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "aegis")]
+use crate::aegis;
+use crate::charset::{LowerCase, Numbers, Symbols, UpperCase};
+use crate::{Algorithm, LessPass, LessPassError, Otp, OtpProvisioning, Settings};
+
+/// A list of [`Profile`], everything needed to derive the passwords except the master
+/// password.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Keyring {
+    /// The profiles, in insertion order.
+    pub profiles: Vec<Profile>,
+}
+
+impl Keyring {
+    /// Find the profile named `name`.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Export the profiles to the JSON backup format of the official LessPass applications.
+    ///
+    /// ## Notes
+    ///
+    /// The format cannot store the algorithm, the iterations or the OTP of a profile: they
+    /// are lost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lesspass_otp::keyring::{Keyring, Profile};
+    ///
+    /// let keyring = Keyring {
+    ///     profiles: vec![Profile::new("lesspass", "lesspass.com", "contact@lesspass.com")],
+    /// };
+    /// let json = keyring.export_lesspass_json();
+    /// assert_eq!(Keyring::import_lesspass_json(&json)?.profiles[0].login, "contact@lesspass.com");
+    ///
+    /// # Ok::<(), lesspass_otp::LessPassError>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn export_lesspass_json(&self) -> String {
+        let profiles = self
+            .profiles
+            .iter()
+            .map(LessPassProfile::from)
+            .collect::<Vec<_>>();
+        serde_json::to_string_pretty(&profiles).unwrap_or_default()
+    }
+
+    /// Import the profiles of a JSON backup of the official LessPass applications.
+    ///
+    /// The backup is either the list of the profiles, or an object holding it in its
+    /// `passwordProfiles` or `results` field. The profiles are named after their site.
+    ///
+    /// # Errors
+    ///
+    /// A [`LessPassError::InvalidBackup`] if `json` is not such a backup, or if a profile is
+    /// not of the version 2 of LessPass: its password would not be the same.
+    #[cfg(feature = "serde")]
+    pub fn import_lesspass_json(json: &str) -> Result<Self, LessPassError> {
+        let backup: LessPassBackup =
+            serde_json::from_str(json).map_err(|_| LessPassError::InvalidBackup)?;
+        let profiles = match backup {
+            LessPassBackup::List(profiles)
+            | LessPassBackup::Wrapped {
+                password_profiles: profiles,
+            } => profiles,
+        };
+        if profiles
+            .iter()
+            .any(|profile| profile.version != default_version())
+        {
+            return Err(LessPassError::InvalidBackup);
+        }
+
+        Ok(Self {
+            profiles: profiles.iter().map(Profile::from).collect(),
+        })
+    }
+
+    /// Export the OTP of the profiles to a vault of Aegis Authenticator, encrypted with
+    /// `password`, so they can be imported by Aegis or by [`import::aegis_json`].
+    ///
+    /// The profiles without OTP are skipped, the others are decrypted with the master
+    /// password.
+    ///
+    /// # Errors
+    ///
+    /// * See [`OtpProfile::decrypt`].
+    /// * [`LessPassError::EncryptionFailed`] if the vault cannot be encrypted.
+    ///
+    /// [`import::aegis_json`]: crate::import::aegis_json
+    #[cfg(feature = "aegis")]
+    pub fn export_aegis(
+        &self,
+        lesspass: &LessPass,
+        password: &str,
+    ) -> Result<String, LessPassError> {
+        let mut entries = Vec::new();
+        for profile in &self.profiles {
+            let otp_profile = match &profile.otp {
+                Some(otp) => otp,
+                None => continue,
+            };
+            let otp = otp_profile.decrypt(lesspass, &profile.site, &profile.login)?;
+            let provisioning = &otp_profile.provisioning;
+            let secret = otp.get_secret().ok_or(LessPassError::HmacProviderFailure)?;
+
+            entries.push(aegis::Entry {
+                kind: if provisioning.get_counter().is_some() {
+                    "hotp"
+                } else {
+                    "totp"
+                }
+                .to_owned(),
+                uuid: aegis::uuid(),
+                name: provisioning.get_label().to_owned(),
+                issuer: provisioning
+                    .get_issuer()
+                    .unwrap_or(&profile.name)
+                    .to_owned(),
+                note: profile.notes.clone().unwrap_or_default(),
+                info: aegis::Info {
+                    secret: base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret),
+                    algo: match otp.get_algorithm() {
+                        Algorithm::SHA256 => "SHA256",
+                        Algorithm::SHA512 => "SHA512",
+                        _ => "SHA1",
+                    }
+                    .to_owned(),
+                    digits: otp.get_digits(),
+                    period: Some(otp.get_period()),
+                    counter: provisioning.get_counter(),
+                },
+            });
+        }
+
+        aegis::Vault::seal(
+            &aegis::Database {
+                version: 2,
+                entries,
+            },
+            password,
+        )
+    }
+}
+
+/// A profile of a LessPass JSON backup.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct LessPassProfile {
+    site: String,
+    login: String,
+    lowercase: bool,
+    uppercase: bool,
+    // Named `digits` by old versions
+    #[serde(alias = "digits")]
+    numbers: bool,
+    symbols: bool,
+    length: u8,
+    counter: u32,
+    #[serde(default = "default_version")]
+    version: u8,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LessPassBackup {
+    List(Vec<LessPassProfile>),
+    Wrapped {
+        #[serde(rename = "passwordProfiles", alias = "results")]
+        password_profiles: Vec<LessPassProfile>,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl From<&Profile> for LessPassProfile {
+    fn from(profile: &Profile) -> Self {
+        Self {
+            site: profile.site.clone(),
+            login: profile.login.clone(),
+            lowercase: profile.lowercase,
+            uppercase: profile.uppercase,
+            numbers: profile.numbers,
+            symbols: profile.symbols,
+            length: profile.length,
+            counter: profile.counter,
+            version: default_version(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&LessPassProfile> for Profile {
+    fn from(profile: &LessPassProfile) -> Self {
+        Self {
+            name: profile.site.clone(),
+            site: profile.site.clone(),
+            login: profile.login.clone(),
+            counter: profile.counter,
+            length: profile.length,
+            lowercase: profile.lowercase,
+            uppercase: profile.uppercase,
+            numbers: profile.numbers,
+            symbols: profile.symbols,
+            ..Self::default()
+        }
+    }
+}
+
+/// Parameters of a password: the site, the login, the counter and the [`Settings`].
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{Algorithm, LessPass};
+/// use lesspass_otp::keyring::Profile;
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let mut profile = Profile::new("lesspass", "lesspass.com", "contact@lesspass.com");
+/// profile.symbols = false;
+///
+/// assert_eq!(profile.password(&lp)?, "OlfK63bmUhqrGODR");
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Profile {
+    /// Name used to find the profile.
+    pub name: String,
+    /// Site of the password.
+    pub site: String,
+    /// Login on the site.
+    pub login: String,
+    /// Counter, to increment to change the password.
+    pub counter: u32,
+    /// Length of the password.
+    pub length: u8,
+    /// Use lowercase letters.
+    pub lowercase: bool,
+    /// Use uppercase letters.
+    pub uppercase: bool,
+    /// Use numbers.
+    pub numbers: bool,
+    /// Use symbols.
+    pub symbols: bool,
+    /// Algorithm, the one of the master password if `None`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub algorithm: Option<Algorithm>,
+    /// Number of iterations, the default one if `None`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub iterations: Option<u32>,
+    /// OTP of the site, with its encrypted secret.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub otp: Option<OtpProfile>,
+    /// Free text attached to the profile.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub notes: Option<String>,
+    /// The password of the site is not the derived one yet, it should be changed.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub needs_rotation: bool,
+    /// Identifier of the profile on a LessPass Database, once it is stored there.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub remote_id: Option<String>,
+}
+
+impl Profile {
+    /// Create a profile with the default settings.
+    #[must_use]
+    pub fn new(name: &str, site: &str, login: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            site: site.to_owned(),
+            login: login.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Get the [`Settings`] of the password.
+    #[must_use]
+    pub fn settings(&self) -> Settings {
+        let mut settings = Settings::new(
+            self.length,
+            if self.lowercase {
+                LowerCase::Using
+            } else {
+                LowerCase::NotUsing
+            },
+            if self.uppercase {
+                UpperCase::Using
+            } else {
+                UpperCase::NotUsing
+            },
+            if self.numbers {
+                Numbers::Using
+            } else {
+                Numbers::NotUsing
+            },
+            if self.symbols {
+                Symbols::Using
+            } else {
+                Symbols::NotUsing
+            },
+        );
+        if let Some(algorithm) = self.algorithm {
+            settings.set_algorithm(algorithm);
+        }
+        if let Some(iterations) = self.iterations {
+            settings.set_iterations(iterations);
+        }
+        settings
+    }
+
+    /// Derive the password of the profile.
+    ///
+    /// # Errors
+    ///
+    /// See [`LessPass::password`].
+    pub fn password(&self, lesspass: &LessPass) -> Result<String, LessPassError> {
+        lesspass.password(&self.site, &self.login, self.counter, &self.settings())
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            site: String::new(),
+            login: String::new(),
+            counter: 1,
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            symbols: true,
+            algorithm: None,
+            iterations: None,
+            otp: None,
+            notes: None,
+            needs_rotation: false,
+            remote_id: None,
+        }
+    }
+}
+
+/// An OTP whose secret is encrypted with the master password, the site and the login.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OtpProfile {
+    /// Secret encrypted with [`LessPass::secret_totp`], or [`LessPass::secret_hotp`] for a
+    /// HOTP.
+    #[cfg_attr(feature = "serde", serde(with = "base64_bytes"))]
+    pub secret: Vec<u8>,
+    /// Number of digits of a token.
+    #[cfg_attr(feature = "serde", serde(default = "default_digits"))]
+    pub digits: u8,
+    /// Algorithm of the HMAC.
+    #[cfg_attr(feature = "serde", serde(default = "default_algorithm"))]
+    pub algorithm: Algorithm,
+    /// Period of validity of a TOTP token.
+    #[cfg_attr(feature = "serde", serde(default = "default_period"))]
+    pub period: u32,
+    /// Metadata displayed by the authenticators, it also identifies a HOTP.
+    pub provisioning: OtpProvisioning,
+}
+
+impl OtpProfile {
+    /// Encrypt the secret of `otp`, a HOTP if the `provisioning` has a counter, a TOTP
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::HmacProviderFailure`] if the secret of `otp` is not known.
+    /// * [`LessPassError::InvalidLength`] if the secret cannot be encrypted.
+    pub fn encrypt(
+        lesspass: &LessPass,
+        site: &str,
+        login: &str,
+        otp: &Otp,
+        provisioning: OtpProvisioning,
+    ) -> Result<Self, LessPassError> {
+        let secret = otp.get_secret().ok_or(LessPassError::HmacProviderFailure)?;
+        // These lengths would be decrypted instead of encrypted
+        if secret.len() == 32 || secret.len() == 64 {
+            return Err(LessPassError::InvalidLength);
+        }
+        let secret = match provisioning.get_counter() {
+            Some(_) => lesspass.secret_hotp(site, login, secret)?,
+            None => lesspass.secret_totp(site, login, secret)?,
+        };
+
+        Ok(Self {
+            secret,
+            digits: otp.get_digits(),
+            algorithm: otp.get_algorithm(),
+            period: otp.get_period(),
+            provisioning,
+        })
+    }
+
+    /// Decrypt the secret, and create the [`Otp`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Otp::new`] and [`LessPass::secret_totp`].
+    pub fn decrypt(
+        &self,
+        lesspass: &LessPass,
+        site: &str,
+        login: &str,
+    ) -> Result<Otp, LessPassError> {
+        let secret = match self.provisioning.get_counter() {
+            Some(_) => lesspass.secret_hotp(site, login, &self.secret)?,
+            None => lesspass.secret_totp(site, login, &self.secret)?,
+        };
+        Otp::new(
+            &secret,
+            self.digits,
+            Some(self.algorithm),
+            Some(self.period),
+            None,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+// Version of the LessPass algorithm implemented by this crate
+#[cfg(feature = "serde")]
+const fn default_version() -> u8 {
+    2
+}
+
+#[cfg(feature = "serde")]
+const fn default_digits() -> u8 {
+    6
+}
+
+#[cfg(feature = "serde")]
+const fn default_algorithm() -> Algorithm {
+    Algorithm::SHA1
+}
+
+#[cfg(feature = "serde")]
+const fn default_period() -> u32 {
+    30
+}
+
+#[cfg(feature = "serde")]
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded.trim()).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_settings() {
+        let mut profile = Profile::new("name", "site", "login");
+        let settings = profile.settings();
+        assert_eq!(settings.get_password_len(), 16);
+        assert_eq!(
+            settings.get_characterset(),
+            Settings::default().get_characterset()
+        );
+        assert!(settings.get_algorithm().is_none());
+
+        profile.length = 20;
+        profile.uppercase = false;
+        profile.algorithm = Some(Algorithm::SHA512);
+        profile.iterations = Some(1_000);
+        let settings = profile.settings();
+        assert_eq!(settings.get_password_len(), 20);
+        assert_eq!(settings.get_characterset().get_charset_count(), 3);
+        assert_eq!(settings.get_algorithm(), Some(Algorithm::SHA512));
+        assert_eq!(settings.get_iterations(), 1_000);
+    }
+
+    #[test]
+    fn find_profile() {
+        let keyring = Keyring {
+            profiles: vec![
+                Profile::new("first", "a.com", "me"),
+                Profile::new("second", "b.com", "me"),
+            ],
+        };
+        assert_eq!(keyring.find("second").unwrap().site, "b.com");
+        assert!(keyring.find("third").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn lesspass_json() {
+        let mut profile = Profile::new("github", "github.com", "me");
+        profile.symbols = false;
+        profile.counter = 3;
+        profile.algorithm = Some(Algorithm::SHA512);
+        let keyring = Keyring {
+            profiles: vec![profile],
+        };
+
+        let json = keyring.export_lesspass_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "site": "github.com",
+                "login": "me",
+                "lowercase": true,
+                "uppercase": true,
+                "numbers": true,
+                "symbols": false,
+                "length": 16,
+                "counter": 3,
+                "version": 2,
+            }])
+        );
+
+        let imported = Keyring::import_lesspass_json(&json).unwrap();
+        assert_eq!(imported.profiles[0].name, "github.com");
+        assert_eq!(imported.profiles[0].counter, 3);
+        assert!(!imported.profiles[0].symbols);
+        // Not stored in the backup
+        assert_eq!(imported.profiles[0].algorithm, None);
+
+        let wrapped = r#"{"passwordProfiles": [{"id": "5", "site": "a.com", "login": "me",
+            "lowercase": true, "uppercase": false, "digits": true, "symbols": false,
+            "length": 12, "counter": 1, "version": 2, "created": "2020-01-01T00:00:00Z"}]}"#;
+        let imported = Keyring::import_lesspass_json(wrapped).unwrap();
+        assert_eq!(imported.profiles[0].length, 12);
+        assert!(imported.profiles[0].numbers && !imported.profiles[0].uppercase);
+
+        assert_eq!(
+            Keyring::import_lesspass_json("{\"profiles\": []}"),
+            Err(LessPassError::InvalidBackup)
+        );
+
+        // The passwords of LessPass v1 are generated differently
+        let v1 = r#"[{"site": "a.com", "login": "me", "lowercase": true, "uppercase": true,
+            "numbers": true, "symbols": true, "length": 12, "counter": 1, "version": 1}]"#;
+        assert_eq!(
+            Keyring::import_lesspass_json(v1),
+            Err(LessPassError::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn otp_round_trip() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let otp = Otp::new(b"12345678901234567890", 8, None, None, None).unwrap();
+
+        let mut provisioning = OtpProvisioning::new("me");
+        let totp =
+            OtpProfile::encrypt(&lesspass, "site", "me", &otp, provisioning.clone()).unwrap();
+        assert_eq!(totp.secret.len(), 32);
+        let decrypted = totp.decrypt(&lesspass, "site", "me").unwrap();
+        assert_eq!(decrypted.totp_from_ts(59), "94287082");
+
+        provisioning.set_counter(Some(0));
+        let hotp = OtpProfile::encrypt(&lesspass, "site", "me", &otp, provisioning).unwrap();
+        assert_ne!(hotp.secret, totp.secret);
+        let decrypted = hotp.decrypt(&lesspass, "site", "me").unwrap();
+        assert_eq!(decrypted.hotp(1), otp.hotp(1));
+
+        let otp = Otp::new(&[0; 32], 6, None, None, None).unwrap();
+        assert_eq!(
+            OtpProfile::encrypt(&lesspass, "site", "me", &otp, OtpProvisioning::new("me")),
+            Err(LessPassError::InvalidLength)
+        );
+    }
+
+    #[cfg(feature = "aegis")]
+    #[test]
+    fn aegis_round_trip() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let otp = Otp::new(
+            b"12345678901234567890",
+            8,
+            Some(Algorithm::SHA256),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut provisioning = OtpProvisioning::new("me");
+        provisioning.set_issuer(Some("GitHub"));
+        let mut profile = Profile::new("github", "github.com", "me");
+        profile.otp =
+            Some(OtpProfile::encrypt(&lesspass, "github.com", "me", &otp, provisioning).unwrap());
+        let keyring = Keyring {
+            profiles: vec![profile, Profile::new("no otp", "example.com", "me")],
+        };
+
+        let vault = keyring.export_aegis(&lesspass, "vault").unwrap();
+        let profiles = crate::import::aegis_json(&vault, "vault", &lesspass)
+            .unwrap()
+            .profiles;
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].site, "GitHub");
+        let imported = profiles[0]
+            .otp
+            .as_ref()
+            .unwrap()
+            .decrypt(&lesspass, "GitHub", "me")
+            .unwrap();
+        assert_eq!(imported.get_algorithm(), Algorithm::SHA256);
+        assert_eq!(imported.totp_from_ts(59), otp.totp_from_ts(59));
+    }
+}
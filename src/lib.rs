@@ -106,27 +106,48 @@
 #[macro_use]
 extern crate lazy_static;
 
-use num_bigint::BigUint;
-
 pub use crate::algo::Algorithm;
-use crate::entropy::Entropy;
+pub use crate::entropy::Entropy;
 pub use crate::errors::LessPassError;
 use crate::fingerprint::Fingerprint;
+pub use crate::generator::Generator;
 use crate::master::Master;
-pub use crate::otp::{decode_base32, Otp};
+pub use crate::otp::{decode_base32, HmacProvider, Otp};
+pub use crate::provisioning::OtpProvisioning;
 pub use crate::settings::Settings;
 use std::ops::Sub;
 
+#[cfg(feature = "aegis")]
+mod aegis;
 mod algo;
+/// Check the passwords against the Have I Been Pwned breaches, without sending them.
+pub mod breach;
 /// Settings to define charset.
 pub mod charset;
+/// Client of the LessPass Database, to synchronise the profiles.
+#[cfg(feature = "client")]
+pub mod client;
+mod csv;
 mod entropy;
 mod errors;
 mod fingerprint;
+mod generator;
 mod hex;
+/// Import the exports of other password managers.
+pub mod import;
+/// Profiles storing the parameters of the passwords, but never the master password.
+pub mod keyring;
 mod master;
 mod otp;
+/// Export to the `pass` password store.
+pub mod pass;
+mod percent;
+mod protobuf;
+mod provisioning;
 mod settings;
+/// Synchronise the keyring between devices, through a remote storage.
+#[cfg(feature = "serde")]
+pub mod sync;
 
 /// The main struct, this is where we define the master password.
 #[derive(Debug)]
@@ -134,10 +155,6 @@ pub struct LessPass<'a> {
     master: Master<'a>,
 }
 
-lazy_static! {
-    static ref BIGINT1: BigUint = BigUint::from(1_u64);
-}
-
 impl<'a> LessPass<'a> {
     /// Define master password to be used with every password.
     ///
@@ -234,49 +251,55 @@ impl<'a> LessPass<'a> {
             return Err(LessPassError::NoCharsetSelected);
         }
 
-        // Generate salt
-        let salt = Entropy::salt(site, login, counter);
-        // Calculate entropy
-        let mut entropy = Entropy::new(algorithm, &self.master, &salt, settings.get_iterations());
-
-        // Generate the password now that all prerequisite is available
-
-        let charset = settings.get_characterset();
-        let chars = charset.get_chars().as_bytes();
-        let max_len = settings.get_password_len() as usize - charset.get_charset_count();
-        let charset_len = BigUint::from(chars.len());
-        let mut password = Vec::with_capacity(settings.get_password_len() as usize);
-
-        // Step 1:
-        // get random char from charset, of password_len - number_of_charset length to generate a
-        // temporary password
-        for _ in 0..max_len {
-            let rem = entropy.consume(&charset_len);
-            password.push(chars[rem]);
-        }
+        self.generate(site, login, counter, settings)
+    }
 
-        // Step 2:
-        // get one character per charset to add later to the password to add later to the
-        // temporary password
-        let mut additional_pass = Vec::with_capacity(charset.get_serials().len());
-        for serial in charset.get_serials() {
-            let rem = entropy.consume(&charset.serial_len(*serial));
-            additional_pass.push(charset.get_serial(*serial).as_bytes()[rem])
+    /// Derive the entropy of the current site, with the algorithm and the iterations of the
+    /// `generator`, and build a password from it with the `generator`.
+    ///
+    /// Contrary to [`LessPass::password`], the length of the password is not validated
+    /// against the algorithm: it is left to the generator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lesspass_otp::{Algorithm, LessPass, Settings};
+    ///
+    /// let lp = LessPass::new("My5ecr3!", Algorithm::SHA256)?;
+    /// let settings = Settings::default();
+    ///
+    /// let pass = lp.generate("example.com", "test@example.com", 1, &settings)?;
+    /// assert_eq!(pass, "38VdYgV3)/x*}`e,");
+    ///
+    /// # Ok::<(), lesspass_otp::LessPassError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::UnsupportedAlgorithm`] in case you want to use an unsupported
+    ///   algorithm.
+    /// * The errors of the [`Generator`].
+    pub fn generate<G: Generator + ?Sized>(
+        &self,
+        site: &str,
+        login: &str,
+        counter: u32,
+        generator: &G,
+    ) -> Result<String, LessPassError> {
+        let algorithm = generator
+            .algorithm()
+            .unwrap_or_else(|| self.master.get_algorithm());
+        // Sha1 cannot be used with LessPass
+        if algorithm == Algorithm::SHA1 {
+            return Err(LessPassError::UnsupportedAlgorithm);
         }
 
-        // Step 3:
-        // add additional characters to the password to generate final password
-        let mut password_len = BigUint::from(password.len());
-        for char in additional_pass {
-            let rem = entropy.consume(&password_len);
-            password.insert(rem, char);
-            password_len += &BIGINT1 as &BigUint;
-        }
+        // Generate salt
+        let salt = Entropy::salt(site, login, counter);
+        // Calculate entropy
+        let mut entropy = Entropy::new(algorithm, &self.master, &salt, generator.iterations());
 
-        Ok(match String::from_utf8(password) {
-            Ok(s) => s,
-            _ => unreachable!(),
-        })
+        generator.generate(&mut entropy)
     }
 
     /// Decode a HOTP secret from aa previous encoded secret, or encode a clear one.
@@ -438,9 +461,9 @@ impl<'a> LessPass<'a> {
     ) -> Result<Vec<u8>, LessPassError> {
         let (algorithm, encrypt) = match secret.len() {
             i if (1..32).contains(&i) => (Algorithm::SHA256, true),
-            i if i == 32 => (Algorithm::SHA256, false),
+            32 => (Algorithm::SHA256, false),
             i if (33..64).contains(&i) => (Algorithm::SHA512, true),
-            i if i == 64 => (Algorithm::SHA512, false),
+            64 => (Algorithm::SHA512, false),
             _ => return Err(LessPassError::InvalidLength),
         };
 
@@ -511,6 +534,12 @@ impl<'a> LessPass<'a> {
         }
         get_fingerprint(s.as_str())
     }
+
+    /// Derive a key of 32 bytes from the master password, dedicated to the `context`.
+    #[cfg(feature = "git")]
+    pub(crate) fn key(&self, context: &[u8]) -> Vec<u8> {
+        Algorithm::SHA256.pbkdf2(self.master.bytes(), context, 100_000)
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,5 @@
+use core::fmt;
+
 use crate::{Algorithm, LessPassError};
 
 /// Decode a base32 encoded string.
@@ -26,7 +28,7 @@ use crate::{Algorithm, LessPassError};
 #[inline]
 pub fn decode_base32(input: &str) -> Result<Vec<u8>, LessPassError> {
     let encoded = input
-        .trim_end_matches(|c| c == '=')
+        .trim_end_matches('=')
         .replace("-", "")
         .replace(" ", "");
 
@@ -37,6 +39,41 @@ pub fn decode_base32(input: &str) -> Result<Vec<u8>, LessPassError> {
     }
 }
 
+/// Compute the HMAC used by [`Otp`] outside of this crate.
+///
+/// Useful when the secret is not available to the application, because it is stored in a
+/// hardware token (like a YubiKey in challenge-response mode) or in an OS keystore.
+/// The [`Otp`] still handles the truncation and the formatting of the token.
+///
+/// # Example
+///
+/// ```
+/// use lesspass_otp::{Algorithm, HmacProvider, LessPassError, Otp};
+///
+/// #[derive(Debug)]
+/// struct Keystore;
+///
+/// impl HmacProvider for Keystore {
+///     fn hmac(&self, algorithm: Algorithm, data: &[u8]) -> Result<Vec<u8>, LessPassError> {
+///         // Ask the keystore to do the computation, the secret never leaves it
+///         Ok(algorithm.hmac(b"12345678901234567890", data))
+///     }
+/// }
+///
+/// let otp = Otp::with_provider(Box::new(Keystore), 6, None, None, None)?;
+/// assert_eq!(otp.try_hotp(0)?, "755224");
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+pub trait HmacProvider: fmt::Debug + Send + Sync {
+    /// Compute the HMAC of `data` with the `algorithm`, using the secret owned by the provider.
+    ///
+    /// # Errors
+    ///
+    /// Return [`LessPassError::HmacProviderFailure`] if the HMAC cannot be computed.
+    fn hmac(&self, algorithm: Algorithm, data: &[u8]) -> Result<Vec<u8>, LessPassError>;
+}
+
 /// Deals with the OTP authentication.
 ///
 /// Can be used to provide `HOTP` or `TOTP`.
@@ -67,6 +104,8 @@ pub fn decode_base32(input: &str) -> Result<Vec<u8>, LessPassError> {
 pub struct Otp {
     // Secret to use
     secret: Vec<u8>,
+    // External HMAC computation, used instead of the secret if defined
+    provider: Option<Box<dyn HmacProvider>>,
     // Algorithm, must be Sha1 (default), Sha2-256 or Sha2-512
     algorithm: Algorithm,
     // Number of digits, 6 (default) or 8
@@ -75,6 +114,8 @@ pub struct Otp {
     period: u32,
     // Timestamp delta for TOTP (0 by default)
     timestamp: u64,
+    // Append the RFC 4226 checksum digit (false by default)
+    checksum: bool,
 }
 
 impl Otp {
@@ -89,7 +130,7 @@ impl Otp {
     ///
     /// # Errors
     ///
-    /// * [`LessPassError::InvalidLength`] if the secret length is not valid.
+    /// * [`LessPassError::InvalidLength`] if the number of `digits` is not valid.
     ///   It must be from `6` to `9`.
     /// * [`LessPassError::UnsupportedAlgorithm`] if the specified algorithm is not supported.
     ///   It must be [`Algorithm::SHA1`] or [`Algorithm::SHA256`] or [`Algorithm::SHA512`],
@@ -112,6 +153,46 @@ impl Otp {
         algorithm: Option<Algorithm>,
         period: Option<u32>,
         timestamp: Option<u64>,
+    ) -> Result<Self, LessPassError> {
+        Self::build(secret.to_vec(), None, digits, algorithm, period, timestamp)
+    }
+
+    /// Create an instance delegating the HMAC computation to a [`HmacProvider`].
+    ///
+    /// The parameters are the same as in [`Otp::new`], except the secret that is owned by
+    /// the `provider`.
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::InvalidLength`] if the number of `digits` is not valid.
+    ///   It must be from `6` to `9`.
+    /// * [`LessPassError::UnsupportedAlgorithm`] if the specified algorithm is not supported.
+    ///   It must be [`Algorithm::SHA1`] or [`Algorithm::SHA256`] or [`Algorithm::SHA512`],
+    ///   anything else is invalid.
+    pub fn with_provider(
+        provider: Box<dyn HmacProvider>,
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+        timestamp: Option<u64>,
+    ) -> Result<Self, LessPassError> {
+        Self::build(
+            Vec::new(),
+            Some(provider),
+            digits,
+            algorithm,
+            period,
+            timestamp,
+        )
+    }
+
+    fn build(
+        secret: Vec<u8>,
+        provider: Option<Box<dyn HmacProvider>>,
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+        timestamp: Option<u64>,
     ) -> Result<Self, LessPassError> {
         match (algorithm, digits) {
             // Allow valid algorithms
@@ -122,11 +203,13 @@ impl Otp {
                 if i > 5 && i < 10 =>
             {
                 Ok(Self {
-                    secret: secret.to_vec(),
-                    algorithm: algorithm.unwrap_or_else(|| Algorithm::SHA1),
+                    secret,
+                    provider,
+                    algorithm: algorithm.unwrap_or(Algorithm::SHA1),
                     digits,
                     period: period.unwrap_or(30).max(1),
                     timestamp: timestamp.unwrap_or(0),
+                    checksum: false,
                 })
             }
             (None, _)
@@ -139,6 +222,156 @@ impl Otp {
         }
     }
 
+    /// Check the compiled crypto stack against the test vectors of the RFC 4226 (HOTP) and
+    /// RFC 6238 (TOTP), for every algorithm supported by [`Otp`].
+    ///
+    /// Applications can run it once at startup, before trusting the displayed codes, for
+    /// example when built for an unusual architecture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lesspass_otp::Otp;
+    ///
+    /// Otp::self_test()?;
+    ///
+    /// # Ok::<(), lesspass_otp::LessPassError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Return [`LessPassError::SelfTestFailed`] with the faulty [`Algorithm`] if a generated
+    /// token does not match the expected one.
+    pub fn self_test() -> Result<(), LessPassError> {
+        // RFC 4226, appendix D
+        const HOTP: [&str; 10] = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        // RFC 6238, appendix B
+        const TIMESTAMPS: [u64; 6] = [
+            59,
+            1_111_111_109,
+            1_111_111_111,
+            1_234_567_890,
+            2_000_000_000,
+            20_000_000_000,
+        ];
+        const TOTP: [(Algorithm, &[u8], [&str; 6]); 3] = [
+            (
+                Algorithm::SHA1,
+                b"12345678901234567890",
+                [
+                    "94287082", "07081804", "14050471", "89005924", "69279037", "65353130",
+                ],
+            ),
+            (
+                Algorithm::SHA256,
+                b"12345678901234567890123456789012",
+                [
+                    "46119246", "68084774", "67062674", "91819424", "90698825", "77737706",
+                ],
+            ),
+            (
+                Algorithm::SHA512,
+                b"1234567890123456789012345678901234567890123456789012345678901234",
+                [
+                    "90693936", "25091201", "99943326", "93441116", "38618901", "47863826",
+                ],
+            ),
+        ];
+
+        let otp = Self::new(
+            b"12345678901234567890",
+            6,
+            Some(Algorithm::SHA1),
+            None,
+            None,
+        )?;
+        for (counter, expected) in HOTP.iter().enumerate() {
+            if otp.hotp(counter as u64) != *expected {
+                return Err(LessPassError::SelfTestFailed(Algorithm::SHA1));
+            }
+        }
+
+        for (algorithm, seed, tokens) in TOTP.iter() {
+            let otp = Self::new(seed, 8, Some(*algorithm), None, None)?;
+            for (timestamp, expected) in TIMESTAMPS.iter().zip(tokens.iter()) {
+                if otp.totp_from_ts(*timestamp) != *expected {
+                    return Err(LessPassError::SelfTestFailed(*algorithm));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a checksum digit to the generated token.
+    ///
+    /// The checksum digit is computed with the Luhn algorithm described in the appendix of
+    /// RFC 4226, and is added after the `digits` length token.
+    ///
+    /// ## Notes
+    ///
+    /// Only some legacy hardware-token validators require it, authenticators apps never
+    /// use it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lesspass_otp::Otp;
+    ///
+    /// let mut otp = Otp::new(b"12345678901234567890", 6, None, None, None)?;
+    /// otp.set_checksum(true);
+    /// let token = otp.hotp(0);
+    ///
+    /// assert_eq!(token, "7552243");
+    ///
+    /// # Ok::<(), lesspass_otp::LessPassError>(())
+    /// ```
+    pub fn set_checksum(&mut self, checksum: bool) {
+        self.checksum = checksum;
+    }
+
+    /// Is the checksum digit appended to the token?
+    #[must_use]
+    pub const fn get_checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Get the secret, or `None` if it is owned by a [`HmacProvider`].
+    #[must_use]
+    pub fn get_secret(&self) -> Option<&[u8]> {
+        match self.provider {
+            Some(_) => None,
+            None => Some(&self.secret),
+        }
+    }
+
+    /// Get the [`Algorithm`].
+    #[must_use]
+    pub const fn get_algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Get the number of digits of the token.
+    #[must_use]
+    pub const fn get_digits(&self) -> u8 {
+        self.digits
+    }
+
+    /// Get the period of validity of a TOTP token, in seconds.
+    #[must_use]
+    pub const fn get_period(&self) -> u32 {
+        self.period
+    }
+
+    /// Get the beginning step of the TOTP, in seconds from Unix Epoch.
+    #[must_use]
+    pub const fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
     /// `[feature = "std_time"]` Retrieve the TOTP code with actual timestamp.
     #[cfg(feature = "std_time")]
     #[must_use]
@@ -153,23 +386,60 @@ impl Otp {
     }
 
     /// Retrieve the TOTP code with time number of seconds
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`HmacProvider`] fails, use [`Otp::try_totp_from_ts`] to handle it.
     #[must_use]
     pub fn totp_from_ts(&self, timestamp: u64) -> String {
+        self.try_totp_from_ts(timestamp)
+            .expect("HMAC provider failed")
+    }
+
+    /// Retrieve the TOTP code with time number of seconds, reporting [`HmacProvider`] failures.
+    ///
+    /// A `timestamp` before the beginning step of the TOTP gives the token of its first step.
+    ///
+    /// # Errors
+    ///
+    /// Return [`LessPassError::HmacProviderFailure`] if the HMAC cannot be computed.
+    pub fn try_totp_from_ts(&self, timestamp: u64) -> Result<String, LessPassError> {
         // Pass to HTOP (same algorithm), with window timestamp as counter
-        self.hotp((timestamp - self.timestamp) / u64::from(self.period))
+        self.try_hotp(timestamp.saturating_sub(self.timestamp) / u64::from(self.period))
     }
 
     /// Retrieve the HOTP code, with `counter` being the current value to use
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`HmacProvider`] fails, use [`Otp::try_hotp`] to handle it.
     #[must_use]
     pub fn hotp(&self, counter: u64) -> String {
+        self.try_hotp(counter).expect("HMAC provider failed")
+    }
+
+    /// Retrieve the HOTP code, with `counter` being the current value to use, reporting
+    /// [`HmacProvider`] failures.
+    ///
+    /// # Errors
+    ///
+    /// Return [`LessPassError::HmacProviderFailure`] if the HMAC cannot be computed, or if
+    /// the computed HMAC is too short to be truncated.
+    pub fn try_hotp(&self, counter: u64) -> Result<String, LessPassError> {
         // compute the HMAC of the selected algorithm
-        let digest = self.algorithm.hmac(&self.secret, &counter.to_be_bytes());
+        let digest = match &self.provider {
+            Some(provider) => provider.hmac(self.algorithm, &counter.to_be_bytes())?,
+            None => self.algorithm.hmac(&self.secret, &counter.to_be_bytes()),
+        };
 
         // Truncate
         let off = (match digest.last() {
             Some(byte) => byte,
-            None => unreachable!(),
+            None => return Err(LessPassError::HmacProviderFailure),
         } & 0xf) as usize;
+        if digest.len() < off + 4 {
+            return Err(LessPassError::HmacProviderFailure);
+        }
         let binary = (u64::from(digest[off]) & 0x7f) << 24
             | (u64::from(digest[off + 1]) & 0xff) << 16
             | (u64::from(digest[off + 2]) & 0xff) << 8
@@ -177,10 +447,36 @@ impl Otp {
         let binary = binary % (10_u64.pow(self.digits.into()));
 
         // Prepend with additional 0 to have digits length Token and convert it to String
-        format!("{:0>1$}", binary, self.digits.into())
+        let token = format!("{:0>1$}", binary, self.digits.into());
+
+        Ok(if self.checksum {
+            format!("{}{}", token, checksum(binary, self.digits))
+        } else {
+            token
+        })
     }
 }
 
+/// Compute the Luhn checksum digit of the `digits` length `num`, as described in RFC 4226.
+fn checksum(mut num: u64, digits: u8) -> u64 {
+    const DOUBLE_DIGITS: [u64; 10] = [0, 2, 4, 6, 8, 1, 3, 5, 7, 9];
+
+    let mut double_digit = true;
+    let mut total = 0;
+    for _ in 0..digits {
+        let digit = num % 10;
+        num /= 10;
+        total += if double_digit {
+            DOUBLE_DIGITS[digit as usize]
+        } else {
+            digit
+        };
+        double_digit = !double_digit;
+    }
+
+    (10 - total % 10) % 10
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,9 +581,66 @@ mod tests {
         assert_eq!(t.hotp(9), "520489");
     }
 
+    #[test]
+    fn self_test() {
+        assert_eq!(Otp::self_test(), Ok(()));
+    }
+
+    #[test]
+    fn luhn_checksum() {
+        assert_eq!(checksum(755_224, 6), 3);
+        assert_eq!(checksum(287_082, 6), 2);
+        assert_eq!(checksum(7_992_739, 7), 8);
+        // Leading zeros are part of the token
+        assert_eq!(checksum(63_323, 6), 0);
+
+        let mut t = Otp::new(b"12345678901234567890", 6, None, None, None).unwrap();
+        assert!(!t.get_checksum());
+        t.set_checksum(true);
+        assert!(t.get_checksum());
+        assert_eq!(t.hotp(0), "7552243");
+        assert_eq!(t.hotp(1), "2870822");
+    }
+
+    #[derive(Debug)]
+    struct Provider(Option<&'static [u8]>);
+
+    impl HmacProvider for Provider {
+        fn hmac(&self, algorithm: Algorithm, data: &[u8]) -> Result<Vec<u8>, LessPassError> {
+            match self.0 {
+                Some(secret) => Ok(algorithm.hmac(secret, data)),
+                None => Err(LessPassError::HmacProviderFailure),
+            }
+        }
+    }
+
+    #[test]
+    fn external_hmac_provider() {
+        let t = Otp::with_provider(
+            Box::new(Provider(Some(b"12345678901234567890"))),
+            8,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(t.try_totp_from_ts(59).unwrap(), "94287082");
+        assert_eq!(t.totp_from_ts(1_111_111_109), "07081804");
+
+        let t = Otp::with_provider(Box::new(Provider(None)), 6, None, None, None).unwrap();
+        assert_eq!(t.try_hotp(0), Err(LessPassError::HmacProviderFailure));
+    }
+
     #[test]
     fn totp() {
         let t = Otp::new(b"1234567890", 9, None, None, None).unwrap();
         assert_eq!(t.totp().len(), 9);
     }
+
+    #[test]
+    fn totp_before_beginning() {
+        let t = Otp::new(b"1234567890", 6, None, None, Some(1000)).unwrap();
+        assert_eq!(t.totp_from_ts(10), t.totp_from_ts(1000));
+        assert_ne!(t.totp_from_ts(10), t.totp_from_ts(1030));
+    }
 }
@@ -0,0 +1,207 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::keyring::{Keyring, Profile};
+use crate::{LessPass, LessPassError};
+
+/// An entry of a [`pass`](https://www.passwordstore.org/) password store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassEntry {
+    /// Path of the entry in the store, without extension, like `github.com/me`.
+    pub path: String,
+    /// Clear content: the password on the first line, then the `otpauth://` URI of the
+    /// OTP, if any, and the `login:` and `url:` metadata.
+    pub content: String,
+}
+
+/// Callback encrypting the clear content of an entry, like `gpg --encrypt`.
+pub type Encrypt<'a> = &'a mut dyn FnMut(&[u8]) -> io::Result<Vec<u8>>;
+
+/// Derive the password of every profile of the `keyring`, and lay them out as `pass`
+/// entries named `site/login`.
+///
+/// The OTP secrets are decrypted, and written as `otpauth://` URIs understood by the
+/// `pass-otp` extension.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::keyring::{Keyring, Profile};
+/// use lesspass_otp::{pass, Algorithm, LessPass};
+///
+/// let lp = LessPass::new("password", Algorithm::SHA256)?;
+/// let mut profile = Profile::new("lesspass", "lesspass.com", "contact@lesspass.com");
+/// profile.symbols = false;
+/// let keyring = Keyring { profiles: vec![profile] };
+///
+/// let entries = pass::export(&keyring, &lp)?;
+/// assert_eq!(entries[0].path, "lesspass.com/contact@lesspass.com");
+/// assert_eq!(
+///     entries[0].content,
+///     "OlfK63bmUhqrGODR\nlogin: contact@lesspass.com\nurl: lesspass.com\n"
+/// );
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+///
+/// # Errors
+///
+/// See [`LessPass::password`] and [`crate::keyring::OtpProfile::decrypt`].
+pub fn export(keyring: &Keyring, lesspass: &LessPass) -> Result<Vec<PassEntry>, LessPassError> {
+    keyring
+        .profiles
+        .iter()
+        .map(|profile| entry(profile, lesspass))
+        .collect()
+}
+
+/// Write the `entries` in the store at `dir`, creating the missing directories.
+///
+/// Every entry is encrypted by `encrypt` into a `.gpg` file, the callback usually runs
+/// `gpg --encrypt` with the recipients of the `.gpg-id` file. Without callback, the entries
+/// are written in clear `.txt` files, to be reviewed or inserted with `pass insert -m`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use std::process::{Command, Stdio};
+/// use std::path::Path;
+///
+/// use lesspass_otp::pass::{self, PassEntry};
+///
+/// # let entries: Vec<PassEntry> = Vec::new();
+/// let mut gpg = |content: &[u8]| -> io::Result<Vec<u8>> {
+///     let mut child = Command::new("gpg")
+///         .args(&["--encrypt", "--recipient", "me@example.com", "--batch"])
+///         .stdin(Stdio::piped())
+///         .stdout(Stdio::piped())
+///         .spawn()?;
+///     child.stdin.take().unwrap().write_all(content)?;
+///     Ok(child.wait_with_output()?.stdout)
+/// };
+/// pass::write(Path::new("/home/me/.password-store"), &entries, Some(&mut gpg))?;
+///
+/// # Ok::<(), io::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Any error of the filesystem or of the `encrypt` callback.
+pub fn write(
+    dir: &Path,
+    entries: &[PassEntry],
+    mut encrypt: Option<Encrypt<'_>>,
+) -> io::Result<()> {
+    for entry in entries {
+        let (extension, data) = match &mut encrypt {
+            Some(encrypt) => ("gpg", encrypt(entry.content.as_bytes())?),
+            None => ("txt", entry.content.as_bytes().to_vec()),
+        };
+        let file = dir.join(format!("{}.{}", entry.path, extension));
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(file, data)?;
+    }
+    Ok(())
+}
+
+/// Lay out the entry of a `profile`.
+fn entry(profile: &Profile, lesspass: &LessPass) -> Result<PassEntry, LessPassError> {
+    let mut content = profile.password(lesspass)?;
+    content.push('\n');
+    if let Some(stored) = &profile.otp {
+        let otp = stored.decrypt(lesspass, &profile.site, &profile.login)?;
+        if let Some(uri) = stored.provisioning.to_uri(&otp) {
+            content.push_str(&uri);
+            content.push('\n');
+        }
+    }
+    content.push_str(&format!(
+        "login: {}\nurl: {}\n",
+        profile.login, profile.site
+    ));
+
+    Ok(PassEntry {
+        path: format!("{}/{}", component(&profile.site), component(&profile.login)),
+        content,
+    })
+}
+
+/// Make `name` a single path component, that cannot escape the store.
+fn component(name: &str) -> String {
+    let name = name.replace(&['/', '\\'][..], "_");
+    if name.is_empty() || name.starts_with('.') {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::keyring::OtpProfile;
+    use crate::{Algorithm, Otp, OtpProvisioning};
+
+    #[test]
+    fn entry_with_otp() {
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let otp = Otp::new(b"12345678901234567890", 6, None, None, None).unwrap();
+        let mut profile = Profile::new("github", "github.com", "me");
+        profile.otp = Some(
+            OtpProfile::encrypt(
+                &lesspass,
+                "github.com",
+                "me",
+                &otp,
+                OtpProvisioning::new("me"),
+            )
+            .unwrap(),
+        );
+
+        let entry = entry(&profile, &lesspass).unwrap();
+        let lines = entry.content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], profile.password(&lesspass).unwrap());
+        assert_eq!(
+            lines[1],
+            "otpauth://totp/me?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&algorithm=SHA1&digits=6&period=30"
+        );
+        assert_eq!(lines[2..], ["login: me", "url: github.com"]);
+    }
+
+    #[test]
+    fn safe_paths() {
+        assert_eq!(component("me@example.com"), "me@example.com");
+        assert_eq!(component("a/b\\c"), "a_b_c");
+        assert_eq!(component(".."), "_..");
+        assert_eq!(component(""), "_");
+    }
+
+    #[test]
+    fn write_store() {
+        let dir = std::env::temp_dir().join(format!("lesspass-otp-pass-{}", std::process::id()));
+        let entries = [PassEntry {
+            path: "example.com/me".to_owned(),
+            content: "secret\n".to_owned(),
+        }];
+
+        write(&dir, &entries, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("example.com/me.txt")).unwrap(),
+            "secret\n"
+        );
+
+        let mut reverse = |content: &[u8]| Ok(content.iter().rev().copied().collect());
+        write(&dir, &entries, Some(&mut reverse)).unwrap();
+        assert_eq!(
+            fs::read(dir.join("example.com/me.gpg")).unwrap(),
+            b"\nterces"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+const HEX: &[u8] = b"0123456789ABCDEF";
+
+/// Percent-encode everything in `input` except the unreserved characters of RFC 3986.
+pub fn encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for &byte in input.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                encoded.push('%');
+                encoded.push(HEX[(byte >> 4) as usize] as char);
+                encoded.push(HEX[(byte & 0x0f) as usize] as char);
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode a percent-encoded `input`, `+` being decoded as a space if `plus_as_space`.
+///
+/// Return `None` if an escape sequence is invalid or if the result is not valid UTF-8.
+pub fn decode(input: &str, plus_as_space: bool) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3)?;
+                // `from_str_radix` would accept a sign
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+                let hex = core::str::from_utf8(hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_reserved() {
+        assert_eq!(
+            encode("ACME Co:john@example.com"),
+            "ACME%20Co%3Ajohn%40example.com"
+        );
+        assert_eq!(encode("a-b.c_d~e"), "a-b.c_d~e");
+        assert_eq!(encode("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn decode_escaped() {
+        assert_eq!(
+            decode("ACME%20Co%3ajohn@example.com", false).unwrap(),
+            "ACME Co:john@example.com"
+        );
+        assert_eq!(decode("a+b", false).unwrap(), "a+b");
+        assert_eq!(decode("a+b", true).unwrap(), "a b");
+        assert_eq!(decode("%C3%A9", false).unwrap(), "é");
+        assert!(decode("%4", false).is_none());
+        assert!(decode("%zz", false).is_none());
+        assert!(decode("%+1", false).is_none());
+        assert!(decode("%-1", false).is_none());
+        assert!(decode("%ff", false).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+use core::convert::TryFrom;
+
+/// Value of a protocol buffers field.
+#[derive(Debug, PartialEq)]
+pub enum Value<'a> {
+    /// Integer, boolean or enumeration.
+    Varint(u64),
+    /// String, bytes or embedded message.
+    Bytes(&'a [u8]),
+}
+
+/// Iterator over the fields of a protocol buffers message.
+///
+/// The fixed-length fields are skipped; the iteration ends with `None` in the item if the
+/// message is malformed.
+#[derive(Debug)]
+pub struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    /// Read the fields of the message encoded in `data`.
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0_u64;
+        for (i, &byte) in self.data.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.data = &self.data[i + 1..];
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn field(&mut self) -> Option<(u64, Value<'a>)> {
+        loop {
+            let key = self.varint()?;
+            let value = match key & 0x07 {
+                0 => Value::Varint(self.varint()?),
+                1 => {
+                    self.bytes(8)?;
+                    continue;
+                }
+                2 => {
+                    let len = usize::try_from(self.varint()?).ok()?;
+                    Value::Bytes(self.bytes(len)?)
+                }
+                5 => {
+                    self.bytes(4)?;
+                    continue;
+                }
+                _ => return None,
+            };
+            return Some((key >> 3, value));
+        }
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Option<(u64, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_none() {
+            // Stop after a malformed field
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_fields() {
+        // 1: 150, 2: "hi", 3: fixed64 (skipped), 4: 1
+        let data = [
+            0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i', 0x19, 0, 0, 0, 0, 0, 0, 0, 0, 0x20, 0x01,
+        ];
+        assert_eq!(
+            Fields::new(&data).collect::<Vec<_>>(),
+            [
+                Some((1, Value::Varint(150))),
+                Some((2, Value::Bytes(b"hi"))),
+                Some((4, Value::Varint(1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        // Length beyond the end
+        assert_eq!(Fields::new(&[0x12, 0x05, b'h']).collect::<Vec<_>>(), [None]);
+        // Unterminated varint
+        assert_eq!(Fields::new(&[0x08, 0x96]).collect::<Vec<_>>(), [None]);
+    }
+}
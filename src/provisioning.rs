@@ -0,0 +1,500 @@
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+
+use crate::percent;
+use crate::protobuf::{Fields, Value};
+use crate::{decode_base32, Algorithm, LessPassError, Otp};
+
+/// Metadata describing an [`Otp`] to an authenticator application.
+///
+/// It is read from, and written to, the `otpauth://` provisioning URIs (the content of the
+/// QR codes displayed by the websites), so front-ends can display the account with its
+/// label, issuer and icon.
+///
+/// A HOTP is identified by its initial `counter`, a TOTP has none.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::{Algorithm, OtpProvisioning};
+///
+/// let uri = "otpauth://totp/ACME%20Co:john@example.com?secret=JBSWY3DPEHPK3PXP&issuer=ACME%20Co";
+/// let (otp, provisioning) = OtpProvisioning::from_uri(uri)?;
+/// assert_eq!(provisioning.get_label(), "john@example.com");
+/// assert_eq!(provisioning.get_issuer(), Some("ACME Co"));
+/// assert_eq!(otp.get_algorithm(), Algorithm::SHA1);
+/// assert_eq!(otp.get_digits(), 6);
+///
+/// // Emit it back
+/// let uri = provisioning.to_uri(&otp).unwrap();
+/// assert_eq!(
+///     uri,
+///     "otpauth://totp/ACME%20Co:john%40example.com?secret=JBSWY3DPEHPK3PXP\
+///      &issuer=ACME%20Co&algorithm=SHA1&digits=6&period=30"
+/// );
+///
+/// # Ok::<(), lesspass_otp::LessPassError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OtpProvisioning {
+    // Account name
+    label: String,
+    // Provider or service the account belongs to
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    issuer: Option<String>,
+    // Icon of the account
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    image_url: Option<String>,
+    // Initial counter of a HOTP, None for a TOTP
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    counter: Option<u64>,
+}
+
+impl OtpProvisioning {
+    /// Create the metadata of a TOTP for the account named `label`.
+    #[must_use]
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            issuer: None,
+            image_url: None,
+            counter: None,
+        }
+    }
+
+    /// Parse an `otpauth://` URI, returning the [`Otp`] and its metadata.
+    ///
+    /// The `algorithm`, `digits` and `period` parameters are optional, and default to
+    /// `SHA1`, `6` and `30`. The issuer is taken from the `issuer` parameter, or from the
+    /// label prefix if missing. The `image` parameter is used as the icon URL.
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::InvalidUri`] if the URI is malformed, or is not a `totp` or `hotp`
+    ///   one.
+    /// * [`LessPassError::InvalidBase32`] if the secret is not a valid base32 string.
+    /// * [`LessPassError::InvalidLength`] if the number of digits is not valid.
+    /// * [`LessPassError::UnsupportedAlgorithm`] if the algorithm is not supported.
+    pub fn from_uri(uri: &str) -> Result<(Otp, Self), LessPassError> {
+        const SCHEME: &str = "otpauth://";
+
+        // The URI is untrusted, it may not even be ASCII
+        if !uri
+            .get(..SCHEME.len())
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        {
+            return Err(LessPassError::InvalidUri);
+        }
+        let uri = &uri[SCHEME.len()..];
+
+        let (kind, uri) = uri.split_once('/').ok_or(LessPassError::InvalidUri)?;
+        let hotp = match kind.to_ascii_lowercase().as_str() {
+            "totp" => false,
+            "hotp" => true,
+            _ => return Err(LessPassError::InvalidUri),
+        };
+        let (label, query) = uri.split_once('?').unwrap_or((uri, ""));
+        let label = percent::decode(label, false).ok_or(LessPassError::InvalidUri)?;
+
+        let mut provisioning = match label.split_once(':') {
+            Some((issuer, account)) => {
+                let mut provisioning = Self::new(account.trim_start());
+                provisioning.set_issuer(Some(issuer));
+                provisioning
+            }
+            None => Self::new(&label),
+        };
+
+        let mut secret = None;
+        let mut algorithm = Algorithm::SHA1;
+        let mut digits = 6;
+        let mut period = 30;
+        let mut counter = 0;
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = percent::decode(value, true).ok_or(LessPassError::InvalidUri)?;
+            match key {
+                "secret" => secret = Some(decode_base32(&value.to_ascii_uppercase())?),
+                "issuer" => provisioning.set_issuer(Some(&value)),
+                "image" => provisioning.set_image_url(Some(&value)),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => Algorithm::SHA1,
+                        "SHA256" => Algorithm::SHA256,
+                        "SHA512" => Algorithm::SHA512,
+                        _ => return Err(LessPassError::UnsupportedAlgorithm),
+                    }
+                }
+                "digits" => digits = value.parse().map_err(|_| LessPassError::InvalidLength)?,
+                "period" => period = value.parse().map_err(|_| LessPassError::InvalidUri)?,
+                "counter" => counter = value.parse().map_err(|_| LessPassError::InvalidUri)?,
+                // Unknown parameters are ignored
+                _ => {}
+            }
+        }
+
+        if hotp {
+            provisioning.set_counter(Some(counter));
+        }
+        let secret = secret.ok_or(LessPassError::InvalidUri)?;
+        let otp = Otp::new(&secret, digits, Some(algorithm), Some(period), None)?;
+
+        Ok((otp, provisioning))
+    }
+
+    /// Parse an `otpauth-migration://offline?data=…` URI, the batch export of Google
+    /// Authenticator, returning every [`Otp`] and its metadata.
+    ///
+    /// A large export is split in several QR codes, each of them must be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lesspass_otp::OtpProvisioning;
+    ///
+    /// let uri = "otpauth-migration://offline?data=CiQKCkhlbGxvId6tvu8SEGpvaG5AZXhhbXBsZS5jb20gASgBMAIQARgBIAA%3D";
+    /// let otps = OtpProvisioning::from_migration_uri(uri)?;
+    /// assert_eq!(otps.len(), 1);
+    /// assert_eq!(otps[0].1.get_label(), "john@example.com");
+    /// assert_eq!(otps[0].0.get_secret(), Some(&b"Hello!\xde\xad\xbe\xef"[..]));
+    ///
+    /// # Ok::<(), lesspass_otp::LessPassError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * [`LessPassError::InvalidUri`] if the URI or its payload is malformed.
+    /// * [`LessPassError::UnsupportedAlgorithm`] if an OTP uses MD5.
+    pub fn from_migration_uri(uri: &str) -> Result<Vec<(Otp, Self)>, LessPassError> {
+        const SCHEME: &str = "otpauth-migration://offline?";
+
+        if !uri
+            .get(..SCHEME.len())
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        {
+            return Err(LessPassError::InvalidUri);
+        }
+        let data = uri[SCHEME.len()..]
+            .split('&')
+            .find_map(|param| param.strip_prefix("data="))
+            .ok_or(LessPassError::InvalidUri)?;
+        // A `+` of the base64 data is not a space
+        let data = percent::decode(data, false).ok_or(LessPassError::InvalidUri)?;
+        let payload = MIGRATION_BASE64
+            .decode(data.trim())
+            .map_err(|_| LessPassError::InvalidUri)?;
+
+        let mut otps = Vec::new();
+        for field in Fields::new(&payload) {
+            if let (1, Value::Bytes(parameters)) = field.ok_or(LessPassError::InvalidUri)? {
+                otps.push(Self::from_migration_parameters(parameters)?);
+            }
+        }
+        Ok(otps)
+    }
+
+    /// Parse an `OtpParameters` message of a migration payload.
+    fn from_migration_parameters(parameters: &[u8]) -> Result<(Otp, Self), LessPassError> {
+        let mut secret: &[u8] = &[];
+        let mut name = String::new();
+        let mut issuer = String::new();
+        let mut algorithm = Algorithm::SHA1;
+        let mut digits = 6;
+        let mut hotp = false;
+        let mut counter = 0;
+
+        for field in Fields::new(parameters) {
+            match field.ok_or(LessPassError::InvalidUri)? {
+                (1, Value::Bytes(bytes)) => secret = bytes,
+                (2, Value::Bytes(bytes)) => name = utf8(bytes)?,
+                (3, Value::Bytes(bytes)) => issuer = utf8(bytes)?,
+                (4, Value::Varint(value)) => {
+                    algorithm = match value {
+                        0 | 1 => Algorithm::SHA1,
+                        2 => Algorithm::SHA256,
+                        3 => Algorithm::SHA512,
+                        _ => return Err(LessPassError::UnsupportedAlgorithm),
+                    }
+                }
+                (5, Value::Varint(value)) => digits = if value == 2 { 8 } else { 6 },
+                (6, Value::Varint(value)) => hotp = value == 1,
+                (7, Value::Varint(value)) => counter = value,
+                // Unknown fields are ignored
+                _ => {}
+            }
+        }
+
+        // The name may be prefixed by the issuer, like in the `otpauth://` URIs
+        let label = match name.split_once(':') {
+            Some((prefix, account)) if issuer.is_empty() || prefix == issuer => {
+                if issuer.is_empty() {
+                    issuer = prefix.to_owned();
+                }
+                account.trim_start()
+            }
+            _ => name.as_str(),
+        };
+        let mut provisioning = Self::new(label);
+        if !issuer.is_empty() {
+            provisioning.set_issuer(Some(&issuer));
+        }
+        if hotp {
+            provisioning.set_counter(Some(counter));
+        }
+
+        if secret.is_empty() {
+            return Err(LessPassError::InvalidUri);
+        }
+        let otp = Otp::new(secret, digits, Some(algorithm), Some(30), None)?;
+        Ok((otp, provisioning))
+    }
+
+    /// Generate the `otpauth://` URI of the `otp`, to be displayed as a QR code.
+    ///
+    /// Return `None` if the secret of the `otp` is not known, because it is owned by a
+    /// [`crate::HmacProvider`].
+    #[must_use]
+    pub fn to_uri(&self, otp: &Otp) -> Option<String> {
+        let secret = otp.get_secret()?;
+        let secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret);
+
+        let mut uri = String::from("otpauth://");
+        uri.push_str(match self.counter {
+            Some(_) => "hotp/",
+            None => "totp/",
+        });
+        if let Some(issuer) = &self.issuer {
+            uri.push_str(&percent::encode(issuer));
+            uri.push(':');
+        }
+        uri.push_str(&percent::encode(&self.label));
+
+        uri.push_str("?secret=");
+        uri.push_str(&secret);
+        if let Some(issuer) = &self.issuer {
+            uri.push_str("&issuer=");
+            uri.push_str(&percent::encode(issuer));
+        }
+        uri.push_str("&algorithm=");
+        uri.push_str(match otp.get_algorithm() {
+            Algorithm::SHA256 => "SHA256",
+            Algorithm::SHA512 => "SHA512",
+            _ => "SHA1",
+        });
+        uri.push_str(&format!("&digits={}", otp.get_digits()));
+        match self.counter {
+            Some(counter) => uri.push_str(&format!("&counter={}", counter)),
+            None => uri.push_str(&format!("&period={}", otp.get_period())),
+        }
+        if let Some(image_url) = &self.image_url {
+            uri.push_str("&image=");
+            uri.push_str(&percent::encode(image_url));
+        }
+
+        Some(uri)
+    }
+
+    /// Get the account name.
+    #[must_use]
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+
+    /// Change the account name.
+    pub fn set_label(&mut self, label: &str) {
+        self.label = label.to_owned();
+    }
+
+    /// Get the provider or service the account belongs to.
+    #[must_use]
+    pub fn get_issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    /// Change the provider or service the account belongs to.
+    pub fn set_issuer(&mut self, issuer: Option<&str>) {
+        self.issuer = issuer.map(str::to_owned);
+    }
+
+    /// Get the URL of the account icon.
+    #[must_use]
+    pub fn get_image_url(&self) -> Option<&str> {
+        self.image_url.as_deref()
+    }
+
+    /// Change the URL of the account icon.
+    pub fn set_image_url(&mut self, image_url: Option<&str>) {
+        self.image_url = image_url.map(str::to_owned);
+    }
+
+    /// Get the initial counter of a HOTP, or `None` for a TOTP.
+    #[must_use]
+    pub const fn get_counter(&self) -> Option<u64> {
+        self.counter
+    }
+
+    /// Define the initial counter of a HOTP, or `None` for a TOTP.
+    pub fn set_counter(&mut self, counter: Option<u64>) {
+        self.counter = counter;
+    }
+}
+
+/// Base64 of the migration payloads, padded or not.
+const MIGRATION_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Decode a protocol buffers string.
+fn utf8(bytes: &[u8]) -> Result<String, LessPassError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| LessPassError::InvalidUri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_totp() {
+        let (otp, provisioning) = OtpProvisioning::from_uri(
+            "otpauth://totp/Example:alice@google.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=Example&algorithm=SHA256&digits=8&period=60&image=https%3A%2F%2Fexample.com%2Fi.png",
+        )
+        .unwrap();
+        assert_eq!(provisioning.get_label(), "alice@google.com");
+        assert_eq!(provisioning.get_issuer(), Some("Example"));
+        assert_eq!(
+            provisioning.get_image_url(),
+            Some("https://example.com/i.png")
+        );
+        assert_eq!(provisioning.get_counter(), None);
+        assert_eq!(otp.get_secret(), Some(&b"12345678901234567890"[..]));
+        assert_eq!(otp.get_algorithm(), Algorithm::SHA256);
+        assert_eq!(otp.get_digits(), 8);
+        assert_eq!(otp.get_period(), 60);
+    }
+
+    #[test]
+    fn parse_hotp() {
+        let (otp, provisioning) = OtpProvisioning::from_uri(
+            "OTPAUTH://HOTP/alice?secret=gezdgnbvgy3tqojqgezdgnbvgy3tqojq&counter=42",
+        )
+        .unwrap();
+        assert_eq!(provisioning.get_label(), "alice");
+        assert_eq!(provisioning.get_issuer(), None);
+        assert_eq!(provisioning.get_counter(), Some(42));
+        assert_eq!(otp.hotp(0), "755224");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let invalid = [
+            "http://totp/alice?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://motp/alice?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://totp/alice",
+            "otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&period=never",
+            // A multibyte character across the end of the scheme
+            "otpauth:/ééé",
+            "é",
+        ];
+        for uri in invalid.iter() {
+            assert_eq!(
+                OtpProvisioning::from_uri(uri).err(),
+                Some(LessPassError::InvalidUri)
+            );
+        }
+        assert_eq!(
+            OtpProvisioning::from_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&algorithm=MD5")
+                .err(),
+            Some(LessPassError::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            OtpProvisioning::from_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&digits=4")
+                .err(),
+            Some(LessPassError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let otp = Otp::new(
+            b"12345678901234567890",
+            6,
+            Some(Algorithm::SHA512),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut provisioning = OtpProvisioning::new("bob");
+        provisioning.set_issuer(Some("Big Corp"));
+        provisioning.set_image_url(Some("https://example.com/logo.png"));
+        provisioning.set_counter(Some(3));
+
+        let uri = provisioning.to_uri(&otp).unwrap();
+        assert_eq!(
+            uri,
+            "otpauth://hotp/Big%20Corp:bob?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=Big%20Corp&algorithm=SHA512&digits=6&counter=3\
+             &image=https%3A%2F%2Fexample.com%2Flogo.png"
+        );
+
+        let (parsed, parsed_provisioning) = OtpProvisioning::from_uri(&uri).unwrap();
+        assert_eq!(parsed_provisioning, provisioning);
+        assert_eq!(parsed.get_algorithm(), otp.get_algorithm());
+        assert_eq!(parsed.hotp(3), otp.hotp(3));
+    }
+
+    #[test]
+    fn parse_migration() {
+        let otps = OtpProvisioning::from_migration_uri(
+            "otpauth-migration://offline?data=CiQKCkhlbGxvId6tvu8SEGpvaG5AZXhhbXBsZS5jb20gASgBMAIK\
+             KgoKSGVsbG8h3q2%2B7xILQUNNRSBDbzpib2IaB0FDTUUgQ28gAigCMAE4BRAB",
+        )
+        .unwrap();
+        assert_eq!(otps.len(), 2);
+
+        let (otp, provisioning) = &otps[0];
+        assert_eq!(provisioning, &OtpProvisioning::new("john@example.com"));
+        assert_eq!(otp.get_algorithm(), Algorithm::SHA1);
+        assert_eq!(otp.get_digits(), 6);
+        assert_eq!(otp.get_period(), 30);
+
+        let (otp, provisioning) = &otps[1];
+        assert_eq!(provisioning.get_label(), "bob");
+        assert_eq!(provisioning.get_issuer(), Some("ACME Co"));
+        assert_eq!(provisioning.get_counter(), Some(5));
+        assert_eq!(otp.get_algorithm(), Algorithm::SHA256);
+        assert_eq!(otp.get_digits(), 8);
+        assert_eq!(otp.get_secret(), Some(&b"Hello!\xde\xad\xbe\xef"[..]));
+    }
+
+    #[test]
+    fn invalid_migration() {
+        for uri in &[
+            "otpauth://totp/me?secret=JBSWY3DPEHPK3PXP",
+            "otpauth-migration://offline?other=1",
+            "otpauth-migration://offline?data=not%20base64",
+            // Truncated payload
+            "otpauth-migration://offline?data=CiQKCkhlbGxv",
+            // A multibyte character across the end of the scheme
+            "otpauth-migration://offliéééééééé",
+        ] {
+            assert_eq!(
+                OtpProvisioning::from_migration_uri(uri).err(),
+                Some(LessPassError::InvalidUri)
+            );
+        }
+    }
+}
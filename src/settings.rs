@@ -61,7 +61,7 @@ impl Settings {
     /// Get number of iterations configured, or default value.
     #[must_use]
     pub fn get_iterations(&self) -> u32 {
-        self.iterations.unwrap_or_else(|| 100_000)
+        self.iterations.unwrap_or(100_000)
     }
 
     /// Get password length.
@@ -76,6 +76,29 @@ impl Settings {
         &self.char_set
     }
 
+    /// Estimate the strength of the derived passwords, in bits.
+    ///
+    /// This is the entropy of a password of this length made of random characters of the
+    /// selected sets, an upper bound of the strength against an attacker who knows the
+    /// settings but not the master password.
+    ///
+    /// # Examples
+    /// ```
+    /// use lesspass_otp::Settings;
+    /// use lesspass_otp::charset::{UpperCase, LowerCase, Symbols, Numbers};
+    ///
+    /// let settings = Settings::new(16, LowerCase::NotUsing, UpperCase::NotUsing, Numbers::Using, Symbols::NotUsing);
+    /// assert!((settings.get_entropy_bits() - 53.15).abs() < 0.01);
+    /// ```
+    #[must_use]
+    pub fn get_entropy_bits(&self) -> f64 {
+        let chars = self.char_set.get_chars().len();
+        if chars == 0 {
+            return 0.0;
+        }
+        f64::from(self.pass_len) * (chars as f64).log2()
+    }
+
     /// Change default [`Algorithm`].
     ///
     /// ## Notes
@@ -151,6 +174,22 @@ mod tests {
         assert!(settings.get_algorithm().is_none());
     }
 
+    #[test]
+    fn entropy_bits() {
+        let settings = Settings::default();
+        // 94 characters
+        assert!((settings.get_entropy_bits() - 104.87).abs() < 0.01);
+
+        let settings = Settings::new(
+            16,
+            LowerCase::NotUsing,
+            UpperCase::NotUsing,
+            Numbers::NotUsing,
+            Symbols::NotUsing,
+        );
+        assert_eq!(settings.get_entropy_bits(), 0.0);
+    }
+
     #[test]
     fn store_settings_in_creation() {
         let settings = Settings::new(
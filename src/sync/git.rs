@@ -0,0 +1,394 @@
+use core::convert::TryInto;
+use std::fmt;
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, OsRng};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use git2::{ErrorCode, ObjectType, Oid, PushOptions, RemoteCallbacks, Repository, Signature};
+
+use super::{RemoteStore, SyncError};
+use crate::LessPass;
+
+impl From<git2::Error> for SyncError {
+    fn from(error: git2::Error) -> Self {
+        if error.code() == ErrorCode::NotFastForward {
+            Self::Conflict
+        } else {
+            Self::Backend(Box::new(error))
+        }
+    }
+}
+
+/// Name of the remote the keyring is pushed to.
+const REMOTE: &str = "origin";
+/// Context of the key encrypting the committed files, derived from the master password.
+const KEY_CONTEXT: &[u8] = b"lesspass-otp git";
+/// Length of the AES-GCM nonce, prepended to the encrypted file.
+const NONCE_LEN: usize = 12;
+
+/// Sync the keyring with a file committed in a git repository.
+///
+/// Every push is a commit, so the history of the keyring is kept; its version is the
+/// identifier of the commit, a push is refused if another device committed meanwhile.
+/// The repository is bare, the commits are created without working tree, and pushed to
+/// the `origin` remote if any.
+///
+/// The file is encrypted with AES-256-GCM, by a key derived from the master password: the
+/// sites and logins never appear in the history, but every device must use the same master
+/// password.
+///
+/// ## Notes
+///
+/// Only the local and `file://` remotes are supported, unless the `https` or `ssh`
+/// features of the `git2` crate are enabled by the application.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use lesspass_otp::keyring::{Keyring, Profile};
+/// use lesspass_otp::sync::{GitRepo, RemoteStore};
+/// use lesspass_otp::{Algorithm, LessPass};
+///
+/// let lesspass = LessPass::new("My5ecr3!", Algorithm::SHA256).unwrap();
+/// let path = Path::new("/home/me/.local/share/lesspass");
+/// let repo = GitRepo::open(path, "keyring.json", &lesspass)?;
+/// repo.set_remote("/mnt/backup/lesspass.git")?;
+///
+/// let (mut keyring, version) = match repo.pull()? {
+///     Some((keyring, version)) => (keyring, Some(version)),
+///     None => (Keyring::default(), None),
+/// };
+/// keyring.profiles.push(Profile::new("github", "github.com", "me"));
+/// let version = repo.push(&keyring, version.as_deref())?;
+///
+/// # Ok::<(), lesspass_otp::sync::SyncError>(())
+/// ```
+pub struct GitRepo {
+    repo: Repository,
+    file: String,
+    branch: String,
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for GitRepo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GitRepo")
+            .field("path", &self.repo.path())
+            .field("file", &self.file)
+            .field("branch", &self.branch)
+            .finish()
+    }
+}
+
+impl GitRepo {
+    /// Open the repository at `path`, creating a bare one if it does not exist, storing
+    /// the keyring in the `file` at its root, on the `main` branch, encrypted with the
+    /// master password of `lesspass`.
+    ///
+    /// # Errors
+    ///
+    /// A [`SyncError::Backend`] if the repository cannot be opened nor created.
+    pub fn open(path: &Path, file: &str, lesspass: &LessPass<'_>) -> Result<Self, SyncError> {
+        let repo = match Repository::open(path) {
+            Ok(repo) => repo,
+            Err(error) if error.code() == ErrorCode::NotFound => Repository::init_bare(path)?,
+            Err(error) => return Err(error.into()),
+        };
+        Ok(Self {
+            repo,
+            file: file.to_owned(),
+            branch: "main".to_owned(),
+            key: lesspass.key(KEY_CONTEXT),
+        })
+    }
+
+    /// Use the `branch` instead of `main`.
+    pub fn set_branch(&mut self, branch: &str) {
+        self.branch = branch.to_owned();
+    }
+
+    /// Push to, and pull from, the repository at `url`.
+    ///
+    /// # Errors
+    ///
+    /// A [`SyncError::Backend`] if the remote cannot be configured.
+    pub fn set_remote(&self, url: &str) -> Result<(), SyncError> {
+        match self.repo.find_remote(REMOTE) {
+            Ok(_) => self.repo.remote_set_url(REMOTE, url)?,
+            Err(error) if error.code() == ErrorCode::NotFound => {
+                self.repo.remote(REMOTE, url)?;
+            }
+            Err(error) => return Err(error.into()),
+        }
+        Ok(())
+    }
+
+    /// Commit the `data` of the file, or its removal if `None`, on top of the commit
+    /// `version`, and push it to the remote.
+    fn commit(
+        &self,
+        data: Option<&[u8]>,
+        version: Option<&str>,
+        message: &str,
+    ) -> Result<String, SyncError> {
+        self.fetch()?;
+        let tip = self.tip()?;
+        let expected = version
+            .map(Oid::from_str)
+            .transpose()
+            .map_err(|_| SyncError::Conflict)?;
+        if tip != expected {
+            return Err(SyncError::Conflict);
+        }
+        let parent = tip.map(|tip| self.repo.find_commit(tip)).transpose()?;
+
+        let parent_tree = parent.as_ref().map(|parent| parent.tree()).transpose()?;
+        let mut tree = self.repo.treebuilder(parent_tree.as_ref())?;
+        match data {
+            Some(data) => {
+                tree.insert(&self.file, self.repo.blob(data)?, 0o100_644)?;
+            }
+            None => tree.remove(&self.file)?,
+        }
+        let tree = self.repo.find_tree(tree.write()?)?;
+
+        let signature = self
+            .repo
+            .signature()
+            .or_else(|_| Signature::now("lesspass-otp", "lesspass-otp@localhost"))?;
+        let parents = parent.iter().collect::<Vec<_>>();
+        let commit = self
+            .repo
+            .commit(None, &signature, &signature, message, &tree, &parents)?;
+        let local = format!("refs/heads/{}", self.branch);
+        self.repo
+            .reference(&local, commit, true, "lesspass-otp: push")?;
+
+        if let Some(mut remote) = self.remote()? {
+            let mut rejected = false;
+            {
+                let mut callbacks = RemoteCallbacks::new();
+                callbacks.push_update_reference(|_, status| {
+                    rejected |= status.is_some();
+                    Ok(())
+                });
+                let mut options = PushOptions::new();
+                options.remote_callbacks(callbacks);
+                remote.push(&[format!("{0}:{0}", local)], Some(&mut options))?;
+            }
+            if rejected {
+                return Err(SyncError::Conflict);
+            }
+        }
+        Ok(commit.to_string())
+    }
+
+    /// Encrypt the `data` with a random nonce, prepended to the result.
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, SyncError> {
+        let mut nonce = [0; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let encrypted = Aes256Gcm::new_from_slice(&self.key)
+            .ok()
+            .and_then(|cipher| cipher.encrypt(&Nonce::from(nonce), data).ok())
+            // Only a too long keyring could not be encrypted
+            .ok_or(SyncError::InvalidData)?;
+        Ok([&nonce[..], &encrypted].concat())
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, SyncError> {
+        if data.len() < NONCE_LEN {
+            return Err(SyncError::InvalidData);
+        }
+        let (nonce, encrypted) = data.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| SyncError::InvalidData)?;
+        Aes256Gcm::new_from_slice(&self.key)
+            .ok()
+            .and_then(|cipher| cipher.decrypt(&Nonce::from(nonce), encrypted).ok())
+            .ok_or(SyncError::DecryptionFailed)
+    }
+
+    fn remote(&self) -> Result<Option<git2::Remote<'_>>, SyncError> {
+        match self.repo.find_remote(REMOTE) {
+            Ok(remote) => Ok(Some(remote)),
+            Err(error) if error.code() == ErrorCode::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn fetch(&self) -> Result<(), SyncError> {
+        if let Some(mut remote) = self.remote()? {
+            let refspec = format!("+refs/heads/{0}:refs/remotes/{1}/{0}", self.branch, REMOTE);
+            remote.fetch(&[refspec], None, None)?;
+        }
+        Ok(())
+    }
+
+    /// Last commit of the branch, the remote one if there is a remote.
+    fn tip(&self) -> Result<Option<Oid>, SyncError> {
+        let name = if self.remote()?.is_some() {
+            format!("refs/remotes/{}/{}", REMOTE, self.branch)
+        } else {
+            format!("refs/heads/{}", self.branch)
+        };
+        match self.repo.find_reference(&name) {
+            Ok(reference) => Ok(Some(reference.peel(ObjectType::Commit)?.id())),
+            Err(error) if error.code() == ErrorCode::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl RemoteStore for GitRepo {
+    /// Fetch the remote, then read and decrypt the last committed file with the identifier
+    /// of the commit, `None` if it has never been committed.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::DecryptionFailed`] if the file was encrypted with another master
+    ///   password.
+    /// * [`SyncError::Backend`] if the remote cannot be fetched, or the repository read.
+    fn get(&self) -> Result<Option<(Vec<u8>, String)>, SyncError> {
+        self.fetch()?;
+        let commit = match self.tip()? {
+            Some(tip) => self.repo.find_commit(tip)?,
+            None => return Ok(None),
+        };
+        let entry = match commit.tree()?.get_name(&self.file) {
+            Some(entry) => entry.to_object(&self.repo)?,
+            None => return Ok(None),
+        };
+        let blob = entry.peel_to_blob()?;
+        Ok(Some((
+            self.decrypt(blob.content())?,
+            commit.id().to_string(),
+        )))
+    }
+
+    /// Commit the encrypted `data` on top of the commit `version` of the last pull, or as
+    /// the first commit of the branch when `version` is `None`, and push it to the remote.
+    /// Return the identifier of the new commit.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::Conflict`] if another device committed meanwhile.
+    /// * [`SyncError::Backend`] if the remote cannot be fetched or pushed to.
+    fn put(&self, data: &[u8], version: Option<&str>) -> Result<String, SyncError> {
+        self.commit(Some(&self.encrypt(data)?), version, "Update the keyring")
+    }
+
+    /// Commit the removal of the file on top of the commit `version`, its history is kept.
+    ///
+    /// # Errors
+    ///
+    /// See [`put`](Self::put).
+    fn delete(&self, version: &str) -> Result<(), SyncError> {
+        self.commit(None, Some(version), "Delete the keyring")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::keyring::{Keyring, Profile};
+    use crate::Algorithm;
+
+    /// Empty directory of the test, in the temporary directory.
+    fn directory(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("lesspass-otp-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn local_history() {
+        let path = directory("git-local");
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+        let repo = GitRepo::open(&path, "keyring.json", &lesspass).unwrap();
+        assert!(repo.pull().unwrap().is_none());
+
+        let mut keyring = Keyring {
+            profiles: vec![Profile::new("github", "github.com", "me")],
+        };
+        let first = repo.push(&keyring, None).unwrap();
+        assert!(matches!(
+            repo.push(&keyring, None),
+            Err(SyncError::Conflict)
+        ));
+
+        keyring.profiles[0].counter = 2;
+        let second = repo.push(&keyring, Some(&first)).unwrap();
+        assert!(matches!(
+            repo.push(&keyring, Some(&first)),
+            Err(SyncError::Conflict)
+        ));
+
+        // Reopened, with its history
+        let repo = GitRepo::open(&path, "keyring.json", &lesspass).unwrap();
+        let (pulled, version) = repo.pull().unwrap().unwrap();
+        assert_eq!(pulled, keyring);
+        assert_eq!(version, second);
+        let commit = repo
+            .repo
+            .find_commit(Oid::from_str(&second).unwrap())
+            .unwrap();
+        assert_eq!(commit.parent_id(0).unwrap().to_string(), first);
+
+        // Only the encrypted keyring is committed
+        let tree = commit.tree().unwrap();
+        let entry = tree.get_name("keyring.json").unwrap();
+        let blob = entry.to_object(&repo.repo).unwrap().peel_to_blob().unwrap();
+        let content = String::from_utf8_lossy(blob.content());
+        assert!(!content.contains("github"));
+        let other = LessPass::new("other", Algorithm::SHA256).unwrap();
+        let stranger = GitRepo::open(&path, "keyring.json", &other).unwrap();
+        assert!(matches!(stranger.pull(), Err(SyncError::DecryptionFailed)));
+
+        repo.delete(&second).unwrap();
+        assert!(repo.pull().unwrap().is_none());
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn two_devices() {
+        let path = directory("git-remote");
+        let server = path.join("server.git");
+        Repository::init_bare(&server).unwrap();
+        let url = server.to_str().unwrap();
+        let lesspass = LessPass::new("password", Algorithm::SHA256).unwrap();
+
+        let laptop = GitRepo::open(&path.join("laptop"), "keyring.json", &lesspass).unwrap();
+        laptop.set_remote(url).unwrap();
+        let phone = GitRepo::open(&path.join("phone"), "keyring.json", &lesspass).unwrap();
+        phone.set_remote(url).unwrap();
+
+        let mut keyring = Keyring {
+            profiles: vec![Profile::new("github", "github.com", "me")],
+        };
+        let first = laptop.push(&keyring, None).unwrap();
+
+        let (pulled, version) = phone.pull().unwrap().unwrap();
+        assert_eq!(pulled, keyring);
+        assert_eq!(version, first);
+        keyring.profiles[0].counter = 2;
+        phone.push(&keyring, Some(&version)).unwrap();
+
+        // The laptop did not pull the change of the phone
+        assert!(matches!(
+            laptop.push(&keyring, Some(&first)),
+            Err(SyncError::Conflict)
+        ));
+        assert_eq!(laptop.pull().unwrap().unwrap().0.profiles[0].counter, 2);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+use std::{error, fmt};
+
+use crate::keyring::{Keyring, Profile};
+
+#[cfg(feature = "git")]
+pub use self::git::GitRepo;
+#[cfg(feature = "webdav")]
+pub use self::webdav::WebDav;
+
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "webdav")]
+mod webdav;
+
+/// Errors that can be returned by the sync backends.
+#[derive(Debug)]
+pub enum SyncError {
+    /// The remote keyring changed since it was read: it must be pulled and merged before
+    /// pushing again.
+    Conflict,
+
+    /// The remote file is not a serialized keyring.
+    InvalidData,
+
+    /// The remote keyring cannot be decrypted, it was encrypted with another master
+    /// password.
+    DecryptionFailed,
+
+    /// The server answered with this unsuccessful HTTP status.
+    Status(u16),
+
+    /// The server did not return the version of the file.
+    MissingVersion,
+
+    /// The backend failed, like a network or an I/O error.
+    Backend(Box<dyn error::Error + Send + Sync>),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Conflict => f.write_str("The remote keyring changed since it was read."),
+            Self::InvalidData => f.write_str("The remote file is not a valid keyring."),
+            Self::DecryptionFailed => {
+                f.write_str("The remote keyring cannot be decrypted with this master password.")
+            }
+            Self::Status(status) => write!(f, "The server answered with status {}.", status),
+            Self::MissingVersion => f.write_str("The server did not return the file version."),
+            Self::Backend(error) => write!(f, "The sync backend failed: {}", error),
+        }
+    }
+}
+
+impl error::Error for SyncError {}
+
+/// Number of times [`sync`] pulls and merges again when another device pushed meanwhile.
+const SYNC_ATTEMPTS: usize = 3;
+
+/// A remote storage of the serialized keyring, like a file of a WebDAV server, an object
+/// of S3 or a file of Dropbox.
+///
+/// The stored content has a version, an opaque token changing on each write, like an
+/// ETag: a write must be refused if the version is not the expected one, so the changes
+/// of another device are never overwritten. The backends implement [`get`](Self::get),
+/// [`put`](Self::put) and [`delete`](Self::delete); the keyrings are pulled, pushed and
+/// merged by the provided methods and by [`sync`].
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+///
+/// use lesspass_otp::keyring::{Keyring, Profile};
+/// use lesspass_otp::sync::{RemoteStore, SyncError};
+///
+/// /// Store the keyring in memory, the version being the number of writes.
+/// struct Memory(RefCell<Option<(Vec<u8>, u32)>>);
+///
+/// impl RemoteStore for Memory {
+///     fn get(&self) -> Result<Option<(Vec<u8>, String)>, SyncError> {
+///         let stored = self.0.borrow();
+///         Ok(stored.as_ref().map(|(data, version)| (data.clone(), version.to_string())))
+///     }
+///
+///     fn put(&self, data: &[u8], version: Option<&str>) -> Result<String, SyncError> {
+///         let mut stored = self.0.borrow_mut();
+///         let current = stored.as_ref().map(|(_, version)| version.to_string());
+///         if current.as_deref() != version {
+///             return Err(SyncError::Conflict);
+///         }
+///         let next = stored.as_ref().map_or(1, |(_, version)| version + 1);
+///         *stored = Some((data.to_vec(), next));
+///         Ok(next.to_string())
+///     }
+///
+///     fn delete(&self, version: &str) -> Result<(), SyncError> {
+///         let mut stored = self.0.borrow_mut();
+///         match stored.as_ref() {
+///             Some((_, current)) if current.to_string() == version => *stored = None,
+///             _ => return Err(SyncError::Conflict),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let store = Memory(RefCell::new(None));
+/// let keyring = Keyring {
+///     profiles: vec![Profile::new("github", "github.com", "me")],
+/// };
+/// let version = store.push(&keyring, None)?;
+/// assert_eq!(store.pull()?, Some((keyring, version)));
+///
+/// # Ok::<(), SyncError>(())
+/// ```
+pub trait RemoteStore {
+    /// Read the stored content with its version, `None` if nothing is stored.
+    ///
+    /// # Errors
+    ///
+    /// Any error of the backend.
+    fn get(&self) -> Result<Option<(Vec<u8>, String)>, SyncError>;
+
+    /// Write the `data` if the stored content still has the `version`, or if nothing is
+    /// stored when `version` is `None`, and return the new version.
+    ///
+    /// # Errors
+    ///
+    /// A [`SyncError::Conflict`] if the stored content has another version, or any error
+    /// of the backend.
+    fn put(&self, data: &[u8], version: Option<&str>) -> Result<String, SyncError>;
+
+    /// Delete the stored content if it still has the `version`.
+    ///
+    /// # Errors
+    ///
+    /// A [`SyncError::Conflict`] if the stored content has another version, or any error
+    /// of the backend.
+    fn delete(&self, version: &str) -> Result<(), SyncError>;
+
+    /// Read the stored keyring with its version, `None` if it has never been pushed.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::InvalidData`] if the stored content is not a keyring.
+    /// * Any error of [`get`](Self::get).
+    fn pull(&self) -> Result<Option<(Keyring, String)>, SyncError> {
+        self.get()?
+            .map(|(data, version)| Ok((decode(&data)?, version)))
+            .transpose()
+    }
+
+    /// Write the `keyring` if the stored one still has the `version` of the last pull, and
+    /// return the new version.
+    ///
+    /// # Errors
+    ///
+    /// See [`put`](Self::put).
+    fn push(&self, keyring: &Keyring, version: Option<&str>) -> Result<String, SyncError> {
+        self.put(&encode(keyring), version)
+    }
+}
+
+/// A profile changed differently on both sides of a [`merge`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Name of the profile.
+    pub name: String,
+    /// The local profile, `None` if it has been deleted locally; this is the one kept.
+    pub local: Option<Profile>,
+    /// The remote profile, `None` if it has been deleted remotely.
+    pub remote: Option<Profile>,
+}
+
+/// Result of a [`merge`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Merge {
+    /// The merged keyring.
+    pub keyring: Keyring,
+    /// The profiles changed on both sides, to be resolved by the user.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merge the `local` and the `remote` keyrings, both derived from the `base` one, the
+/// keyring of the last sync.
+///
+/// The profiles are identified by their name. A profile changed, added or deleted on one
+/// side only has this change in the merged keyring. A profile changed differently on both
+/// sides is a [`MergeConflict`], the local profile is kept. The profiles are in the order
+/// of the remote keyring, followed by the local additions.
+///
+/// # Examples
+///
+/// ```
+/// use lesspass_otp::keyring::{Keyring, Profile};
+/// use lesspass_otp::sync::merge;
+///
+/// let base = Keyring {
+///     profiles: vec![Profile::new("github", "github.com", "me")],
+/// };
+/// let mut local = base.clone();
+/// local.profiles[0].counter = 2;
+/// let mut remote = base.clone();
+/// remote.profiles.push(Profile::new("gitlab", "gitlab.com", "me"));
+///
+/// let merged = merge(&base, &local, &remote);
+/// assert!(merged.conflicts.is_empty());
+/// assert_eq!(merged.keyring.profiles.len(), 2);
+/// assert_eq!(merged.keyring.find("github").unwrap().counter, 2);
+/// ```
+#[must_use]
+pub fn merge(base: &Keyring, local: &Keyring, remote: &Keyring) -> Merge {
+    let mut names: Vec<&str> = Vec::new();
+    for profile in remote.profiles.iter().chain(&local.profiles) {
+        if !names.contains(&profile.name.as_str()) {
+            names.push(&profile.name);
+        }
+    }
+
+    let mut merged = Merge::default();
+    for name in names {
+        let (ancestor, ours, theirs) = (base.find(name), local.find(name), remote.find(name));
+        let profile = if ours == ancestor {
+            theirs
+        } else if theirs == ancestor || ours == theirs {
+            ours
+        } else {
+            merged.conflicts.push(MergeConflict {
+                name: name.to_owned(),
+                local: ours.cloned(),
+                remote: theirs.cloned(),
+            });
+            ours
+        };
+        merged.keyring.profiles.extend(profile.cloned());
+    }
+    merged
+}
+
+/// Synchronise the `local` keyring with the one of the `store`: pull it, [`merge`] it, and
+/// push the result. Return the merge, whose keyring is the `base` of the next sync, and
+/// the new version.
+///
+/// If another device pushed meanwhile, the keyring is pulled and merged again.
+///
+/// # Errors
+///
+/// * [`SyncError::Conflict`] if the store kept changing.
+/// * Any error of [`RemoteStore::pull`] or [`RemoteStore::push`].
+pub fn sync<S: RemoteStore + ?Sized>(
+    store: &S,
+    base: &Keyring,
+    local: &Keyring,
+) -> Result<(Merge, String), SyncError> {
+    for _ in 0..SYNC_ATTEMPTS {
+        let (merged, version) = match store.pull()? {
+            Some((remote, version)) => (merge(base, local, &remote), Some(version)),
+            None => (
+                Merge {
+                    keyring: local.clone(),
+                    conflicts: Vec::new(),
+                },
+                None,
+            ),
+        };
+        match store.push(&merged.keyring, version.as_deref()) {
+            Ok(version) => return Ok((merged, version)),
+            Err(SyncError::Conflict) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(SyncError::Conflict)
+}
+
+/// Serialize the `keyring` to be stored remotely.
+#[must_use]
+pub fn encode(keyring: &Keyring) -> Vec<u8> {
+    serde_json::to_vec_pretty(keyring).unwrap_or_default()
+}
+
+/// Deserialize a keyring stored remotely.
+///
+/// # Errors
+///
+/// A [`SyncError::InvalidData`] if `data` is not a serialized keyring.
+pub fn decode(data: &[u8]) -> Result<Keyring, SyncError> {
+    serde_json::from_slice(data).map_err(|_| SyncError::InvalidData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::{Cell, RefCell};
+
+    /// Store in memory, pushing `intrusions` other keyrings before the next writes.
+    struct Memory {
+        stored: RefCell<Option<(Vec<u8>, u32)>>,
+        intrusions: Cell<u32>,
+    }
+
+    impl RemoteStore for Memory {
+        fn get(&self) -> Result<Option<(Vec<u8>, String)>, SyncError> {
+            let stored = self.stored.borrow();
+            Ok(stored
+                .as_ref()
+                .map(|(data, version)| (data.clone(), version.to_string())))
+        }
+
+        fn put(&self, data: &[u8], version: Option<&str>) -> Result<String, SyncError> {
+            let mut stored = self.stored.borrow_mut();
+            let next = stored.as_ref().map_or(1, |(_, version)| version + 1);
+            if self.intrusions.get() > 0 {
+                self.intrusions.set(self.intrusions.get() - 1);
+                let mut other = decode(&stored.as_ref().unwrap().0).unwrap();
+                other
+                    .profiles
+                    .push(Profile::new(&format!("other {}", next), "b.com", "me"));
+                *stored = Some((encode(&other), next));
+                return Err(SyncError::Conflict);
+            }
+            if stored
+                .as_ref()
+                .map(|(_, version)| version.to_string())
+                .as_deref()
+                != version
+            {
+                return Err(SyncError::Conflict);
+            }
+            *stored = Some((data.to_vec(), next));
+            Ok(next.to_string())
+        }
+
+        fn delete(&self, version: &str) -> Result<(), SyncError> {
+            let mut stored = self.stored.borrow_mut();
+            match stored.as_ref() {
+                Some((_, current)) if current.to_string() == version => *stored = None,
+                _ => return Err(SyncError::Conflict),
+            }
+            Ok(())
+        }
+    }
+
+    fn keyring(profiles: &[(&str, u32)]) -> Keyring {
+        Keyring {
+            profiles: profiles
+                .iter()
+                .map(|&(name, counter)| {
+                    let mut profile = Profile::new(name, "a.com", "me");
+                    profile.counter = counter;
+                    profile
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn merge_three_way() {
+        let base = keyring(&[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1)]);
+        // b changed, c deleted, d changed too, f added
+        let local = keyring(&[("a", 1), ("b", 2), ("d", 2), ("e", 1), ("f", 1)]);
+        // a changed, d changed differently, e deleted, g added
+        let remote = keyring(&[("g", 1), ("a", 3), ("b", 1), ("c", 1), ("d", 3)]);
+
+        let merged = merge(&base, &local, &remote);
+        assert_eq!(
+            merged.keyring,
+            keyring(&[("g", 1), ("a", 3), ("b", 2), ("d", 2), ("f", 1)])
+        );
+        assert_eq!(
+            merged.conflicts,
+            [MergeConflict {
+                name: "d".to_owned(),
+                local: local.find("d").cloned(),
+                remote: remote.find("d").cloned(),
+            }]
+        );
+
+        // Same change on both sides
+        let merged = merge(&base, &local, &local);
+        assert_eq!(merged.keyring, local);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn sync_with_store() {
+        let store = Memory {
+            stored: RefCell::new(None),
+            intrusions: Cell::new(0),
+        };
+        let base = Keyring::default();
+        let local = keyring(&[("a", 1)]);
+        let (merged, version) = sync(&store, &base, &local).unwrap();
+        assert_eq!(merged.keyring, local);
+        assert_eq!(version, "1");
+
+        // Two other devices push while syncing
+        store.intrusions.set(2);
+        let base = merged.keyring;
+        let local = keyring(&[("a", 2)]);
+        let (merged, version) = sync(&store, &base, &local).unwrap();
+        assert_eq!(version, "4");
+        assert_eq!(merged.keyring.profiles.len(), 3);
+        assert_eq!(merged.keyring.find("a").unwrap().counter, 2);
+        assert_eq!(store.pull().unwrap().unwrap().0, merged.keyring);
+
+        store.intrusions.set(SYNC_ATTEMPTS as u32);
+        assert!(matches!(
+            sync(&store, &merged.keyring, &merged.keyring),
+            Err(SyncError::Conflict)
+        ));
+
+        assert!(matches!(store.delete("4"), Err(SyncError::Conflict)));
+        store.delete("7").unwrap();
+        assert!(store.pull().unwrap().is_none());
+    }
+
+    #[test]
+    fn encode_decode() {
+        let keyring = keyring(&[("github", 1)]);
+        assert_eq!(decode(&encode(&keyring)).unwrap(), keyring);
+        assert!(matches!(decode(b"<html>"), Err(SyncError::InvalidData)));
+    }
+}
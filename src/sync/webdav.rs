@@ -0,0 +1,296 @@
+use std::fmt;
+
+use reqwest::blocking::{Client as HttpClient, RequestBuilder, Response};
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::StatusCode;
+
+use super::{RemoteStore, SyncError};
+
+impl From<reqwest::Error> for SyncError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Backend(Box::new(error))
+    }
+}
+
+/// Sync the keyring with a file of a WebDAV server, like Nextcloud or ownCloud.
+///
+/// The ETag of the file is used for optimistic concurrency: the keyring is only pushed if
+/// the file has not changed since it was pulled, so the changes made by another device are
+/// never overwritten.
+///
+/// # Examples
+///
+/// ```no_run
+/// use lesspass_otp::keyring::{Keyring, Profile};
+/// use lesspass_otp::sync::{RemoteStore, WebDav};
+///
+/// let mut webdav = WebDav::new("https://cloud.example.com/remote.php/dav/files/me/lesspass.json");
+/// webdav.set_credentials("me", "application password");
+///
+/// let (mut keyring, etag) = match webdav.pull()? {
+///     Some((keyring, etag)) => (keyring, Some(etag)),
+///     None => (Keyring::default(), None),
+/// };
+/// keyring.profiles.push(Profile::new("github", "github.com", "me"));
+/// let etag = webdav.push(&keyring, etag.as_deref())?;
+///
+/// # Ok::<(), lesspass_otp::sync::SyncError>(())
+/// ```
+pub struct WebDav {
+    url: String,
+    http: HttpClient,
+    credentials: Option<(String, String)>,
+}
+
+impl fmt::Debug for WebDav {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the password
+        f.debug_struct("WebDav")
+            .field("url", &self.url)
+            .field(
+                "user",
+                &self.credentials.as_ref().map(|(user, _)| user.as_str()),
+            )
+            .finish()
+    }
+}
+
+impl WebDav {
+    /// Create a backend storing the keyring in the file at `url`.
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            http: HttpClient::new(),
+            credentials: None,
+        }
+    }
+
+    /// Authenticate the requests with the `user` and the `password`, usually an application
+    /// password.
+    pub fn set_credentials(&mut self, user: &str, password: &str) {
+        self.credentials = Some((user.to_owned(), password.to_owned()));
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.credentials {
+            Some((user, password)) => request.basic_auth(user, Some(password)),
+            None => request,
+        }
+    }
+}
+
+impl RemoteStore for WebDav {
+    /// Download the file with its ETag, `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::Status`] if the request is refused.
+    /// * [`SyncError::MissingVersion`] if the server does not support the ETags.
+    fn get(&self) -> Result<Option<(Vec<u8>, String)>, SyncError> {
+        let response = self.authorized(self.http.get(&self.url)).send()?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response)?;
+        let etag = etag(&response).ok_or(SyncError::MissingVersion)?;
+        Ok(Some((response.bytes()?.to_vec(), etag)))
+    }
+
+    /// Upload the `data` if the file still has the ETag `version`, or if it does not exist
+    /// yet when `version` is `None`, and return the new ETag.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::Conflict`] if the file has been changed by another device.
+    /// * [`SyncError::Status`] if the request is refused.
+    /// * [`SyncError::MissingVersion`] if the server does not support the ETags.
+    fn put(&self, data: &[u8], version: Option<&str>) -> Result<String, SyncError> {
+        let request = self
+            .authorized(self.http.put(&self.url))
+            .header(CONTENT_TYPE, "application/json")
+            .body(data.to_vec());
+        let request = match version {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+
+        let response = request.send()?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(SyncError::Conflict);
+        }
+        if let Some(etag) = etag(&check(response)?) {
+            return Ok(etag);
+        }
+        // Not every server returns the ETag of an uploaded file
+        let response = check(self.authorized(self.http.head(&self.url)).send()?)?;
+        etag(&response).ok_or(SyncError::MissingVersion)
+    }
+
+    /// Delete the file if it still has the ETag `version`.
+    ///
+    /// # Errors
+    ///
+    /// * [`SyncError::Conflict`] if the file has been changed by another device.
+    /// * [`SyncError::Status`] if the request is refused.
+    fn delete(&self, version: &str) -> Result<(), SyncError> {
+        let request = self
+            .authorized(self.http.delete(&self.url))
+            .header(IF_MATCH, version);
+        let response = request.send()?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(SyncError::Conflict);
+        }
+        check(response)?;
+        Ok(())
+    }
+}
+
+fn etag(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// Turn an unsuccessful response into an error.
+fn check(response: Response) -> Result<Response, SyncError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(SyncError::Status(response.status().as_u16()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::keyring::{Keyring, Profile};
+
+    /// Serve a single WebDAV file on a random local port, with numbered ETags.
+    fn server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/dav/lesspass.json",
+            listener.local_addr().unwrap()
+        );
+        thread::spawn(move || {
+            // Content and version of the file
+            let mut file: Option<(Vec<u8>, u32)> = None;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let current = file.as_ref().map(|(_, version)| format!("\"{}\"", version));
+                let header = |name: &str| {
+                    head.lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .map(str::trim)
+                };
+                let method = head.split(' ').next().unwrap();
+                let (status, etag, response) =
+                    if header("authorization:") != Some("Basic bWU6c2VjcmV0") {
+                        ("401 Unauthorized", None, Vec::new())
+                    } else {
+                        match (method, file.clone()) {
+                            ("GET", Some((content, _))) => ("200 OK", current, content),
+                            ("GET", None) => ("404 Not Found", None, Vec::new()),
+                            ("PUT", _)
+                                if header("if-match:").is_some()
+                                    && header("if-match:") != current.as_deref()
+                                    || header("if-none-match:") == Some("*") && file.is_some() =>
+                            {
+                                ("412 Precondition Failed", None, Vec::new())
+                            }
+                            ("PUT", _) => {
+                                let version = file.as_ref().map_or(1, |(_, version)| version + 1);
+                                file = Some((body, version));
+                                // Only the HEAD request returns the ETag
+                                ("201 Created", None, Vec::new())
+                            }
+                            ("HEAD", _) => ("200 OK", current, Vec::new()),
+                            ("DELETE", _) if header("if-match:") != current.as_deref() => {
+                                ("412 Precondition Failed", None, Vec::new())
+                            }
+                            ("DELETE", _) => {
+                                file = None;
+                                ("204 No Content", None, Vec::new())
+                            }
+                            _ => ("405 Method Not Allowed", None, Vec::new()),
+                        }
+                    };
+
+                let etag = etag.map_or_else(String::new, |etag| format!("ETag: {}\r\n", etag));
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    etag,
+                    response.len(),
+                )
+                .unwrap();
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn push_and_pull() {
+        let mut webdav = WebDav::new(&server());
+        assert!(matches!(webdav.pull(), Err(SyncError::Status(401))));
+        webdav.set_credentials("me", "secret");
+        assert!(!format!("{:?}", webdav).contains("secret"));
+
+        assert!(webdav.pull().unwrap().is_none());
+        let mut keyring = Keyring {
+            profiles: vec![Profile::new("github", "github.com", "me")],
+        };
+        let first = webdav.push(&keyring, None).unwrap();
+        assert_eq!(first, "\"1\"");
+        // Created by another device meanwhile
+        assert!(matches!(
+            webdav.push(&keyring, None),
+            Err(SyncError::Conflict)
+        ));
+
+        let (pulled, etag) = webdav.pull().unwrap().unwrap();
+        assert_eq!(pulled, keyring);
+        assert_eq!(etag, first);
+
+        keyring.profiles[0].counter = 2;
+        let second = webdav.push(&keyring, Some(&first)).unwrap();
+        assert_eq!(second, "\"2\"");
+        assert!(matches!(
+            webdav.push(&keyring, Some(&first)),
+            Err(SyncError::Conflict)
+        ));
+        assert_eq!(webdav.pull().unwrap().unwrap().0.profiles[0].counter, 2);
+
+        assert!(matches!(webdav.delete(&first), Err(SyncError::Conflict)));
+        webdav.delete(&second).unwrap();
+        assert!(webdav.get().unwrap().is_none());
+    }
+}
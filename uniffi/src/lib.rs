@@ -0,0 +1,401 @@
+#![deny(missing_docs)]
+#![deny(missing_debug_implementations)]
+#![deny(trivial_numeric_casts)]
+#![deny(unused_extern_crates)]
+
+//! UniFFI bindings of `lesspass-otp`, used to generate the Kotlin and Swift bindings of the
+//! Android and iOS applications.
+//!
+//! Generate the bindings from the compiled library with:
+//!
+//! ```text
+//! cargo build -p lesspass-otp-uniffi --release
+//! cargo run -p lesspass-otp-uniffi --bin uniffi-bindgen -- generate \
+//!     --library target/release/liblesspass_otp_uniffi.so --language kotlin --out-dir out
+//! ```
+
+use std::sync::Arc;
+
+use lesspass_otp::charset::{LowerCase, Numbers, Symbols, UpperCase};
+
+uniffi::setup_scaffolding!();
+
+/// Hash algorithm, see [`lesspass_otp::Algorithm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Algorithm {
+    /// SHA1, only usable with OTP.
+    Sha1,
+    /// SHA2-256, the algorithm of the canonical LessPass implementation.
+    Sha256,
+    /// SHA2-384.
+    Sha384,
+    /// SHA2-512.
+    Sha512,
+    /// SHA3-256.
+    Sha3_256,
+    /// SHA3-384.
+    Sha3_384,
+    /// SHA3-512.
+    Sha3_512,
+}
+
+impl From<Algorithm> for lesspass_otp::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha1 => Self::SHA1,
+            Algorithm::Sha256 => Self::SHA256,
+            Algorithm::Sha384 => Self::SHA384,
+            Algorithm::Sha512 => Self::SHA512,
+            Algorithm::Sha3_256 => Self::SHA3_256,
+            Algorithm::Sha3_384 => Self::SHA3_384,
+            Algorithm::Sha3_512 => Self::SHA3_512,
+        }
+    }
+}
+
+impl From<lesspass_otp::Algorithm> for Algorithm {
+    fn from(algorithm: lesspass_otp::Algorithm) -> Self {
+        use lesspass_otp::Algorithm as Lib;
+
+        match algorithm {
+            Lib::SHA1 => Self::Sha1,
+            Lib::SHA256 => Self::Sha256,
+            Lib::SHA384 => Self::Sha384,
+            Lib::SHA512 => Self::Sha512,
+            Lib::SHA3_256 => Self::Sha3_256,
+            Lib::SHA3_384 => Self::Sha3_384,
+            Lib::SHA3_512 => Self::Sha3_512,
+        }
+    }
+}
+
+/// Errors returned to the bindings, see [`lesspass_otp::LessPassError`].
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Error)]
+pub enum LessPassError {
+    /// The password is too short.
+    PasswordTooShort {
+        /// Minimum length.
+        min: u8,
+        /// Requested length.
+        length: u8,
+    },
+    /// The password is too long for the algorithm.
+    PasswordTooLong {
+        /// Maximum length.
+        max: u8,
+        /// Requested length.
+        length: u8,
+        /// Algorithm limiting the length.
+        algorithm: Algorithm,
+    },
+    /// No charset is specified.
+    NoCharsetSelected,
+    /// The algorithm is not valid where it is used.
+    UnsupportedAlgorithm,
+    /// The length of the digits or of the secret is not valid.
+    InvalidLength,
+    /// Any other error, with its description.
+    Other {
+        /// Description of the error.
+        message: String,
+    },
+}
+
+impl From<lesspass_otp::LessPassError> for LessPassError {
+    fn from(error: lesspass_otp::LessPassError) -> Self {
+        use lesspass_otp::LessPassError as Error;
+
+        match error {
+            Error::PasswordTooShort(min, length) => Self::PasswordTooShort { min, length },
+            Error::PasswordTooLong(max, length, algorithm) => Self::PasswordTooLong {
+                max,
+                length,
+                algorithm: algorithm.into(),
+            },
+            Error::NoCharsetSelected => Self::NoCharsetSelected,
+            Error::UnsupportedAlgorithm => Self::UnsupportedAlgorithm,
+            Error::InvalidLength => Self::InvalidLength,
+            error => Self::Other {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for LessPassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use lesspass_otp::LessPassError as Error;
+
+        // The same descriptions as the library
+        match self {
+            Self::PasswordTooShort { min, length } => Error::PasswordTooShort(*min, *length).fmt(f),
+            Self::PasswordTooLong {
+                max,
+                length,
+                algorithm,
+            } => Error::PasswordTooLong(*max, *length, (*algorithm).into()).fmt(f),
+            Self::NoCharsetSelected => Error::NoCharsetSelected.fmt(f),
+            Self::UnsupportedAlgorithm => Error::UnsupportedAlgorithm.fmt(f),
+            Self::InvalidLength => Error::InvalidLength.fmt(f),
+            Self::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for LessPassError {}
+
+/// Settings to derive a password, see [`lesspass_otp::Settings`].
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct Settings {
+    /// Length of the password.
+    pub length: u8,
+    /// Use lowercase letters.
+    pub lowercase: bool,
+    /// Use uppercase letters.
+    pub uppercase: bool,
+    /// Use numbers.
+    pub numbers: bool,
+    /// Use symbols.
+    pub symbols: bool,
+    /// Algorithm, the one of the master password if `None`.
+    pub algorithm: Option<Algorithm>,
+    /// Number of iterations, `100000` if `None`.
+    pub iterations: Option<u32>,
+}
+
+impl From<&Settings> for lesspass_otp::Settings {
+    fn from(settings: &Settings) -> Self {
+        let mut converted = Self::new(
+            settings.length,
+            if settings.lowercase {
+                LowerCase::Using
+            } else {
+                LowerCase::NotUsing
+            },
+            if settings.uppercase {
+                UpperCase::Using
+            } else {
+                UpperCase::NotUsing
+            },
+            if settings.numbers {
+                Numbers::Using
+            } else {
+                Numbers::NotUsing
+            },
+            if settings.symbols {
+                Symbols::Using
+            } else {
+                Symbols::NotUsing
+            },
+        );
+        if let Some(algorithm) = settings.algorithm {
+            converted.set_algorithm(algorithm.into());
+        }
+        if let Some(iterations) = settings.iterations {
+            converted.set_iterations(iterations);
+        }
+        converted
+    }
+}
+
+/// Default settings: 16 characters, lower, upper, number and symbol.
+#[uniffi::export]
+pub fn default_settings() -> Settings {
+    Settings {
+        length: 16,
+        lowercase: true,
+        uppercase: true,
+        numbers: true,
+        symbols: true,
+        algorithm: None,
+        iterations: None,
+    }
+}
+
+/// One of the 3 elements of a fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct Fingerprint {
+    /// Color, formatted as `#RRGGBB`.
+    pub color: String,
+    /// Font Awesome icon name.
+    pub icon: String,
+}
+
+/// Master password, see [`lesspass_otp::LessPass`].
+#[derive(Debug, uniffi::Object)]
+pub struct LessPass {
+    master: String,
+    algorithm: lesspass_otp::Algorithm,
+}
+
+#[uniffi::export]
+impl LessPass {
+    /// Define the master password, and the algorithm used by default.
+    #[uniffi::constructor]
+    pub fn new(master: String, algorithm: Algorithm) -> Result<Arc<Self>, LessPassError> {
+        let algorithm = algorithm.into();
+        lesspass_otp::LessPass::new(&master, algorithm)?;
+        Ok(Arc::new(Self { master, algorithm }))
+    }
+
+    /// Derive the password of `login` on `site`.
+    pub fn password(
+        &self,
+        site: String,
+        login: String,
+        counter: u32,
+        settings: Settings,
+    ) -> Result<String, LessPassError> {
+        Ok(self
+            .lesspass()?
+            .password(&site, &login, counter, &(&settings).into())?)
+    }
+
+    /// Encrypt a clear TOTP secret, or decrypt an encrypted one.
+    pub fn secret_totp(
+        &self,
+        site: String,
+        login: String,
+        secret: Vec<u8>,
+    ) -> Result<Vec<u8>, LessPassError> {
+        Ok(self.lesspass()?.secret_totp(&site, &login, &secret)?)
+    }
+
+    /// Encrypt a clear HOTP secret, or decrypt an encrypted one.
+    pub fn secret_hotp(
+        &self,
+        site: String,
+        login: String,
+        secret: Vec<u8>,
+    ) -> Result<Vec<u8>, LessPassError> {
+        Ok(self.lesspass()?.secret_hotp(&site, &login, &secret)?)
+    }
+
+    /// Get the fingerprint of the master password.
+    pub fn fingerprint(&self) -> Result<Vec<Fingerprint>, LessPassError> {
+        Ok(self
+            .lesspass()?
+            .get_fingerprint(b"")
+            .iter()
+            .map(|(color, icon)| Fingerprint {
+                color: (*color).to_owned(),
+                icon: (*icon).to_owned(),
+            })
+            .collect())
+    }
+}
+
+impl LessPass {
+    fn lesspass(&self) -> Result<lesspass_otp::LessPass<'_>, LessPassError> {
+        Ok(lesspass_otp::LessPass::new(&self.master, self.algorithm)?)
+    }
+}
+
+/// HOTP and TOTP generator, see [`lesspass_otp::Otp`].
+#[derive(Debug, uniffi::Object)]
+pub struct Otp(lesspass_otp::Otp);
+
+#[uniffi::export]
+impl Otp {
+    /// Create a generator from a binary `secret`.
+    #[uniffi::constructor]
+    pub fn new(
+        secret: Vec<u8>,
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+        timestamp: Option<u64>,
+    ) -> Result<Arc<Self>, LessPassError> {
+        Ok(Arc::new(Self(lesspass_otp::Otp::new(
+            &secret,
+            digits,
+            algorithm.map(Into::into),
+            period,
+            timestamp,
+        )?)))
+    }
+
+    /// Create a generator from a base32 encoded `secret`.
+    #[uniffi::constructor]
+    pub fn from_base32(
+        secret: String,
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+        timestamp: Option<u64>,
+    ) -> Result<Arc<Self>, LessPassError> {
+        let secret = lesspass_otp::decode_base32(&secret)?;
+        Self::new(secret, digits, algorithm, period, timestamp)
+    }
+
+    /// Get the TOTP token of the current time.
+    pub fn totp(&self) -> String {
+        self.0.totp()
+    }
+
+    /// Get the TOTP token at `timestamp` seconds from Unix Epoch.
+    pub fn totp_from_ts(&self, timestamp: u64) -> String {
+        self.0.totp_from_ts(timestamp)
+    }
+
+    /// Get the HOTP token of `counter`.
+    pub fn hotp(&self, counter: u64) -> String {
+        self.0.hotp(counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password() {
+        let lp = LessPass::new("password".to_owned(), Algorithm::Sha256).unwrap();
+        let mut settings = default_settings();
+        settings.symbols = false;
+        assert_eq!(
+            lp.password(
+                "lesspass.com".to_owned(),
+                "contact@lesspass.com".to_owned(),
+                1,
+                settings.clone()
+            )
+            .unwrap(),
+            "OlfK63bmUhqrGODR"
+        );
+
+        settings.length = 4;
+        assert_eq!(
+            lp.password("site".to_owned(), "login".to_owned(), 1, settings),
+            Err(LessPassError::PasswordTooShort { min: 5, length: 4 })
+        );
+        assert_eq!(
+            LessPassError::PasswordTooShort { min: 5, length: 4 }.to_string(),
+            "Password length cannot be less than 5 characters, it's 4 length"
+        );
+        assert_eq!(
+            lp.fingerprint().unwrap()[0],
+            Fingerprint {
+                color: "#FFB5DA".to_owned(),
+                icon: "fa-flask".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn otp() {
+        let otp = Otp::from_base32(
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned(),
+            6,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(otp.hotp(0), "755224");
+        assert_eq!(
+            Otp::new(vec![], 4, None, None, None).err(),
+            Some(LessPassError::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+#![deny(missing_docs)]
+#![deny(missing_debug_implementations)]
+#![deny(trivial_numeric_casts)]
+#![deny(unused_extern_crates)]
+
+//! JavaScript bindings of `lesspass-otp`, built with `wasm-bindgen`.
+//!
+//! ```text
+//! wasm-pack build wasm --target web
+//! ```
+//!
+//! ```js
+//! import init, { Algorithm, LessPass, Otp, Settings } from "./pkg/lesspass_otp_wasm.js";
+//!
+//! await init();
+//! const lesspass = new LessPass("password", Algorithm.SHA256);
+//! const settings = new Settings(16, true, true, true, false);
+//! lesspass.password("lesspass.com", "contact@lesspass.com", 1, settings); // "OlfK63bmUhqrGODR"
+//! ```
+//!
+//! Errors are thrown as JavaScript `Error`s, whose `name` is the [`LessPassError`] variant,
+//! like `PasswordTooShort`.
+
+use js_sys::{Array, Date, Error, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use lesspass_otp::charset::{LowerCase, Numbers, Symbols, UpperCase};
+use lesspass_otp::LessPassError;
+
+/// Convert a [`LessPassError`] to a JavaScript `Error`, named after the variant.
+fn to_js_error(error: LessPassError) -> JsValue {
+    let js_error = Error::new(&error.to_string());
+    js_error.set_name(error_name(error));
+    js_error.into()
+}
+
+/// Name of the variant of the [`LessPassError`].
+fn error_name(error: LessPassError) -> &'static str {
+    match error {
+        LessPassError::PasswordTooShort(_, _) => "PasswordTooShort",
+        LessPassError::PasswordTooLong(_, _, _) => "PasswordTooLong",
+        LessPassError::NoCharsetSelected => "NoCharsetSelected",
+        LessPassError::UnsupportedAlgorithm => "UnsupportedAlgorithm",
+        LessPassError::InvalidLength => "InvalidLength",
+        LessPassError::InvalidBase32 => "InvalidBase32",
+        LessPassError::HmacProviderFailure => "HmacProviderFailure",
+        LessPassError::InvalidUri => "InvalidUri",
+        LessPassError::SelfTestFailed(_) => "SelfTestFailed",
+        LessPassError::InvalidBackup => "InvalidBackup",
+        LessPassError::DecryptionFailed => "DecryptionFailed",
+        LessPassError::EncryptionFailed => "EncryptionFailed",
+        LessPassError::InvalidCharset => "InvalidCharset",
+    }
+}
+
+/// Hash algorithm, see [`lesspass_otp::Algorithm`].
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Algorithm {
+    /// SHA1, only usable with OTP.
+    SHA1,
+    /// SHA2-256, the algorithm of the canonical LessPass implementation.
+    SHA256,
+    /// SHA2-384.
+    SHA384,
+    /// SHA2-512.
+    SHA512,
+    /// SHA3-256.
+    SHA3_256,
+    /// SHA3-384.
+    SHA3_384,
+    /// SHA3-512.
+    SHA3_512,
+}
+
+impl From<Algorithm> for lesspass_otp::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::SHA1 => Self::SHA1,
+            Algorithm::SHA256 => Self::SHA256,
+            Algorithm::SHA384 => Self::SHA384,
+            Algorithm::SHA512 => Self::SHA512,
+            Algorithm::SHA3_256 => Self::SHA3_256,
+            Algorithm::SHA3_384 => Self::SHA3_384,
+            Algorithm::SHA3_512 => Self::SHA3_512,
+        }
+    }
+}
+
+/// Settings to derive a password, see [`lesspass_otp::Settings`].
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Settings(lesspass_otp::Settings);
+
+#[wasm_bindgen]
+impl Settings {
+    /// Create the settings of a password of `length` characters.
+    #[wasm_bindgen(constructor)]
+    pub fn new(length: u8, lowercase: bool, uppercase: bool, numbers: bool, symbols: bool) -> Self {
+        Self(lesspass_otp::Settings::new(
+            length,
+            if lowercase {
+                LowerCase::Using
+            } else {
+                LowerCase::NotUsing
+            },
+            if uppercase {
+                UpperCase::Using
+            } else {
+                UpperCase::NotUsing
+            },
+            if numbers {
+                Numbers::Using
+            } else {
+                Numbers::NotUsing
+            },
+            if symbols {
+                Symbols::Using
+            } else {
+                Symbols::NotUsing
+            },
+        ))
+    }
+
+    /// Default settings: 16 characters, lower, upper, number and symbol.
+    #[wasm_bindgen(js_name = default)]
+    pub fn default_settings() -> Self {
+        Self(lesspass_otp::Settings::default())
+    }
+
+    /// Change the number of iterations.
+    #[wasm_bindgen(js_name = setIterations)]
+    pub fn set_iterations(&mut self, iterations: u32) {
+        self.0.set_iterations(iterations);
+    }
+
+    /// Change the algorithm.
+    #[wasm_bindgen(js_name = setAlgorithm)]
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.0.set_algorithm(algorithm.into());
+    }
+}
+
+/// Master password, see [`lesspass_otp::LessPass`].
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct LessPass {
+    master: String,
+    algorithm: lesspass_otp::Algorithm,
+}
+
+#[wasm_bindgen]
+impl LessPass {
+    /// Define the master password, and the algorithm used by default.
+    #[wasm_bindgen(constructor)]
+    pub fn new(master: String, algorithm: Algorithm) -> Result<LessPass, JsValue> {
+        let algorithm = algorithm.into();
+        lesspass_otp::LessPass::new(&master, algorithm).map_err(to_js_error)?;
+        Ok(Self { master, algorithm })
+    }
+
+    /// Derive the password of `login` on `site`.
+    pub fn password(
+        &self,
+        site: &str,
+        login: &str,
+        counter: u32,
+        settings: &Settings,
+    ) -> Result<String, JsValue> {
+        self.lesspass()?
+            .password(site, login, counter, &settings.0)
+            .map_err(to_js_error)
+    }
+
+    /// Encrypt a clear TOTP secret, or decrypt an encrypted one.
+    #[wasm_bindgen(js_name = secretTotp)]
+    pub fn secret_totp(&self, site: &str, login: &str, secret: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.lesspass()?
+            .secret_totp(site, login, secret)
+            .map_err(to_js_error)
+    }
+
+    /// Encrypt a clear HOTP secret, or decrypt an encrypted one.
+    #[wasm_bindgen(js_name = secretHotp)]
+    pub fn secret_hotp(&self, site: &str, login: &str, secret: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.lesspass()?
+            .secret_hotp(site, login, secret)
+            .map_err(to_js_error)
+    }
+
+    /// Get the fingerprint of the master password, salted with `salt`, as an array of
+    /// 3 `{ color, icon }` objects.
+    pub fn fingerprint(&self, salt: &str) -> Result<Array, JsValue> {
+        let fingerprint = Array::new();
+        for (color, icon) in self.lesspass()?.get_fingerprint(salt.as_bytes()).iter() {
+            let entry = Object::new();
+            Reflect::set(&entry, &"color".into(), &(*color).into())?;
+            Reflect::set(&entry, &"icon".into(), &(*icon).into())?;
+            fingerprint.push(&entry);
+        }
+        Ok(fingerprint)
+    }
+
+    fn lesspass(&self) -> Result<lesspass_otp::LessPass<'_>, JsValue> {
+        lesspass_otp::LessPass::new(&self.master, self.algorithm).map_err(to_js_error)
+    }
+}
+
+/// HOTP and TOTP generator, see [`lesspass_otp::Otp`].
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Otp(lesspass_otp::Otp);
+
+#[wasm_bindgen]
+impl Otp {
+    /// Create a generator from a binary `secret`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        secret: &[u8],
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+    ) -> Result<Otp, JsValue> {
+        lesspass_otp::Otp::new(secret, digits, algorithm.map(Into::into), period, None)
+            .map(Self)
+            .map_err(to_js_error)
+    }
+
+    /// Create a generator from a base32 encoded `secret`.
+    #[wasm_bindgen(js_name = fromBase32)]
+    pub fn from_base32(
+        secret: &str,
+        digits: u8,
+        algorithm: Option<Algorithm>,
+        period: Option<u32>,
+    ) -> Result<Otp, JsValue> {
+        let secret = lesspass_otp::decode_base32(secret).map_err(to_js_error)?;
+        Self::new(&secret, digits, algorithm, period)
+    }
+
+    /// Create a generator from an `otpauth://` provisioning URI.
+    #[wasm_bindgen(js_name = fromUri)]
+    pub fn from_uri(uri: &str) -> Result<Otp, JsValue> {
+        lesspass_otp::OtpProvisioning::from_uri(uri)
+            .map(|(otp, _)| Self(otp))
+            .map_err(to_js_error)
+    }
+
+    /// Get the TOTP token of the current time, from the JavaScript clock.
+    pub fn totp(&self) -> String {
+        self.totp_from_ts(Date::now() / 1000.)
+    }
+
+    /// Get the TOTP token at `timestamp` seconds from Unix Epoch.
+    #[wasm_bindgen(js_name = totpFromTs)]
+    pub fn totp_from_ts(&self, timestamp: f64) -> String {
+        self.0.totp_from_ts(timestamp as u64)
+    }
+
+    /// Get the HOTP token of `counter`.
+    pub fn hotp(&self, counter: f64) -> String {
+        self.0.hotp(counter as u64)
+    }
+
+    /// Get the number of seconds before the current TOTP token expires.
+    #[wasm_bindgen(js_name = remainingSeconds)]
+    pub fn remaining_seconds(&self) -> u32 {
+        remaining_seconds(&self.0, (Date::now() / 1000.) as u64)
+    }
+}
+
+/// Number of seconds at `now` before the TOTP token of `otp` expires.
+fn remaining_seconds(otp: &lesspass_otp::Otp, now: u64) -> u32 {
+    let period = u64::from(otp.get_period());
+    // The clock may be before the beginning of the TOTP
+    (period - now.saturating_sub(otp.get_timestamp()) % period) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password() {
+        let lesspass = LessPass::new("password".to_owned(), Algorithm::SHA256).unwrap();
+        let settings = Settings::new(16, true, true, true, false);
+        assert_eq!(
+            lesspass
+                .password("lesspass.com", "contact@lesspass.com", 1, &settings)
+                .unwrap(),
+            "OlfK63bmUhqrGODR"
+        );
+    }
+
+    #[test]
+    fn error_names() {
+        assert_eq!(
+            error_name(LessPassError::PasswordTooShort(5, 4)),
+            "PasswordTooShort"
+        );
+        assert_eq!(
+            error_name(LessPassError::SelfTestFailed(lesspass_otp::Algorithm::SHA1)),
+            "SelfTestFailed"
+        );
+        assert_eq!(
+            error_name(LessPassError::DecryptionFailed),
+            "DecryptionFailed"
+        );
+    }
+
+    #[test]
+    fn remaining() {
+        let otp = lesspass_otp::Otp::new(b"1234567890", 6, None, Some(30), Some(100)).unwrap();
+        assert_eq!(remaining_seconds(&otp, 100), 30);
+        assert_eq!(remaining_seconds(&otp, 129), 1);
+        // Clock behind the beginning of the TOTP
+        assert_eq!(remaining_seconds(&otp, 10), 30);
+    }
+}